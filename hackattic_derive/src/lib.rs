@@ -0,0 +1,156 @@
+//! `#[hackattic_challenge(name = "...")]`, which turns a solver function into a
+//! challenge, i.e it generates the `HackatticChallenge` impl and the `register`
+//! function the `hackattic` binary uses as the challenge's entry point.
+//!
+//! ```ignore
+//! #[hackattic_challenge(name = "mini_miner", verify = "verify_nonce")]
+//! fn solve(problem: &Problem) -> Result<Answer, Error> { ... }
+//! ```
+//!
+//! The problem and solution types are taken from the function's signature, and the
+//! challenge struct is named after the challenge(e.g `MiniMiner`).
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate syn;
+#[macro_use]
+extern crate quote;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use syn::{AttributeArgs, FnArg, GenericArgument, Ident, ItemFn, Lit, Meta, NestedMeta, PathArguments, ReturnType, Type};
+
+#[proc_macro_attribute]
+pub fn hackattic_challenge(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let function = parse_macro_input!(input as ItemFn);
+    match expand(args, function) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
+
+struct Args {
+    name: String,
+    verify: Option<Ident>
+}
+
+fn parse_args(args: AttributeArgs) -> syn::Result<Args> {
+    let mut name = None;
+    let mut verify = None;
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(ref pair)) => {
+                let value = match pair.lit {
+                    Lit::Str(ref value) => value,
+                    ref other => return Err(syn::Error::new_spanned(other, "expected a string"))
+                };
+                if pair.ident == "name" {
+                    name = Some(value.value());
+                } else if pair.ident == "verify" {
+                    verify = Some(value.parse::<Ident>()?);
+                } else {
+                    return Err(syn::Error::new_spanned(&pair.ident, "unknown argument, expected `name` or `verify`"));
+                }
+            },
+            other => return Err(syn::Error::new_spanned(other, "expected `name = \"...\"` or `verify = \"...\"`"))
+        }
+    }
+    match name {
+        Some(name) => Ok(Args { name, verify }),
+        None => Err(syn::Error::new(Span::call_site(), "missing the challenge's `name = \"...\"`"))
+    }
+}
+
+/// The `Problem` of `fn solve(problem: &Problem)`
+fn problem_type(function: &ItemFn) -> syn::Result<Type> {
+    let inputs = &function.decl.inputs;
+    if inputs.len() == 1 {
+        if let Some(&FnArg::Captured(ref arg)) = inputs.first().map(|pair| pair.into_value()) {
+            if let Type::Reference(ref reference) = arg.ty {
+                return Ok((*reference.elem).clone());
+            }
+        }
+    }
+    Err(syn::Error::new_spanned(inputs, "a challenge solver takes a single `&Problem` argument"))
+}
+
+/// The `Solution` and `Error` of `-> Result<Solution, Error>`
+fn result_types(function: &ItemFn) -> syn::Result<(Type, Type)> {
+    let output = &function.decl.output;
+    if let ReturnType::Type(_, ref ty) = *output {
+        if let Type::Path(ref path) = **ty {
+            if let Some(segment) = path.path.segments.last().map(|pair| pair.into_value()) {
+                if let PathArguments::AngleBracketed(ref generics) = segment.arguments {
+                    let types = generics.args.iter().filter_map(|arg| match *arg {
+                        GenericArgument::Type(ref ty) => Some(ty.clone()),
+                        _ => None
+                    }).collect::<Vec<_>>();
+                    if segment.ident == "Result" && types.len() == 2 {
+                        return Ok((types[0].clone(), types[1].clone()));
+                    }
+                }
+            }
+        }
+    }
+    Err(syn::Error::new_spanned(output, "a challenge solver returns `Result<Solution, Error>`"))
+}
+
+/// e.g "a_global_presence" to `AGlobalPresence`
+fn struct_name(challenge: &str) -> Ident {
+    let name = challenge.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect::<String>();
+    Ident::new(&name, Span::call_site())
+}
+
+fn expand(args: AttributeArgs, function: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let args = parse_args(args)?;
+    let problem = problem_type(&function)?;
+    let (solution, error) = result_types(&function)?;
+    let solver = &function.ident;
+    let vis = &function.vis;
+    let name = &args.name;
+    let challenge = struct_name(name);
+    let verify = args.verify.map(|verify| quote! {
+        fn verify(problem: &#problem, solution: &#solution) -> ::std::result::Result<(), #error> {
+            #verify(problem, solution)
+        }
+    });
+    Ok(quote! {
+        #function
+
+        #vis struct #challenge;
+
+        impl ::hackattic::HackatticChallenge for #challenge {
+            type Problem = #problem;
+            type Solution = #solution;
+
+            fn make_solution(problem: &#problem) -> ::std::result::Result<#solution, #error> {
+                #solver(problem)
+            }
+
+            fn challenge_name() -> &'static str {
+                #name
+            }
+
+            #verify
+        }
+
+        pub fn register(registry: &mut ::hackattic::Registry) {
+            registry.register::<#challenge>();
+        }
+    })
+}
+
+#[test]
+fn names_struct_after_challenge() {
+    assert_eq!(struct_name("mini_miner").to_string(), "MiniMiner");
+    assert_eq!(struct_name("a_global_presence").to_string(), "AGlobalPresence");
+    assert_eq!(struct_name("the_redis_one").to_string(), "TheRedisOne");
+}
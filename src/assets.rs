@@ -0,0 +1,106 @@
+//! Downloading of the images and blobs problems link to. Downloads are cached on
+//! disk by URL, so reruns(e.g while tuning OCR) don't fetch the same asset again.
+
+use failure::{Error, ResultExt};
+use hex;
+use reqwest::header::ContentLength;
+use ring::digest;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use super::Config;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct AssetConfig {
+    /// Where downloaded assets are kept, caching is disabled when unset
+    pub cache_dir: Option<PathBuf>,
+    /// Downloads larger than this many bytes are aborted
+    pub max_size: u64,
+    pub timeout_secs: u64
+}
+
+impl Default for AssetConfig {
+    fn default() -> Self {
+        AssetConfig {
+            cache_dir: Some(::std::env::temp_dir().join("hackattic_assets")),
+            max_size: 16 * 1024 * 1024,
+            timeout_secs: 30
+        }
+    }
+}
+
+#[derive(Debug, Fail)]
+#[fail(display = "asset {} is larger than the {} bytes limit", url, max_size)]
+pub struct AssetTooLarge {
+    pub url: String,
+    pub max_size: u64
+}
+
+/// The cache file of `url`, named by the URL's SHA-256
+pub fn cache_path(cache_dir: &Path, url: &str) -> PathBuf {
+    let hash = digest::digest(&digest::SHA256, url.as_bytes());
+    cache_dir.join(hex::encode(hash.as_ref()))
+}
+
+/// Downloads `url` with the current `Config`'s client settings, or reads it from the cache.
+pub fn fetch_asset(url: &str) -> Result<Vec<u8>, Error> {
+    fetch_asset_with(&Config::current()?, url)
+}
+
+pub fn fetch_asset_with(config: &Config, url: &str) -> Result<Vec<u8>, Error> {
+    let cached = config.assets.cache_dir.as_ref().map(|dir| cache_path(dir, url));
+    if let Some(ref path) = cached {
+        if path.exists() {
+            debug!("using cached {} for {}", path.display(), url);
+            let mut contents = Vec::new();
+            File::open(path)?.read_to_end(&mut contents)?;
+            return Ok(contents);
+        }
+    }
+    let contents = download(config, url).with_context(|_| format!("couldn't download {}", url))?;
+    if let Some(ref path) = cached {
+        // written aside first, so an interrupted write never looks like a cached asset
+        let partial = path.with_extension("partial");
+        fs::create_dir_all(path.parent().unwrap())?;
+        File::create(&partial)?.write_all(&contents)?;
+        fs::rename(&partial, path)?;
+    }
+    Ok(contents)
+}
+
+fn download(config: &Config, url: &str) -> Result<Vec<u8>, Error> {
+    let max_size = config.assets.max_size;
+    let client = config.proxy.client_builder()?
+        .timeout(Duration::from_secs(config.assets.timeout_secs))
+        .build()?;
+    config.retry.retry("downloading an asset", || {
+        let response = client.get(url).send()?.error_for_status()?;
+        if response.headers().get::<ContentLength>().map_or(false, |length| **length > max_size) {
+            return Err(AssetTooLarge { url: url.to_owned(), max_size }.into());
+        }
+        // the length header may be missing or lie, so the limit is also enforced while reading
+        let mut contents = Vec::new();
+        response.take(max_size + 1).read_to_end(&mut contents)?;
+        if contents.len() as u64 > max_size {
+            return Err(AssetTooLarge { url: url.to_owned(), max_size }.into());
+        }
+        Ok(contents)
+    })
+}
+
+#[test]
+fn reads_cached_assets() {
+    let dir = ::std::env::temp_dir().join("hackattic_assets_test");
+    let url = "http://assets.invalid/image.png";
+    let config = Config {
+        assets: AssetConfig { cache_dir: Some(dir.clone()), ..AssetConfig::default() },
+        ..Config::default()
+    };
+    fs::create_dir_all(&dir).unwrap();
+    File::create(cache_path(&dir, url)).unwrap().write_all(b"cached").unwrap();
+    let contents = fetch_asset_with(&config, url).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(contents, b"cached");
+}
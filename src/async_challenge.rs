@@ -0,0 +1,186 @@
+use failure::Error;
+use futures::{future, Future, Stream};
+use reqwest::unstable::async::Client;
+use serde;
+use serde_json::{self, Value};
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+use tokio_core::reactor::{Core, Handle, Timeout};
+use super::{ChallengeRun, Config, Fixture, SubmissionResult, check_verification};
+use super::error;
+use super::logging;
+use super::retry::{self, ServerError};
+use super::timing::DeadlineExceeded;
+
+pub type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// Resolves like `solve`, but aborts with `DeadlineExceeded` once `deadline` passes.
+fn with_deadline<T: 'static>(challenge: &'static str, solve: BoxFuture<T>, deadline: Option<Duration>, handle: &Handle) -> BoxFuture<T> {
+    let deadline = match deadline {
+        Some(deadline) => deadline,
+        None => return solve
+    };
+    let timeout = match Timeout::new(deadline, handle) {
+        Ok(timeout) => timeout,
+        Err(e) => return Box::new(future::err(e.into()))
+    };
+    let start = Instant::now();
+    let expired = timeout.map_err(Error::from)
+        .and_then(move |_| Err(DeadlineExceeded::new(challenge, start.elapsed(), deadline).into()));
+    Box::new(solve.select(expired)
+        .map(|(solution, _)| solution)
+        .map_err(|(e, _)| e))
+}
+
+/// Like `HackatticChallenge`, but fetching, solving and submitting are futures ran on a
+/// single event loop. Anything a solver spawns on that loop keeps running while the
+/// solution is being submitted, so solvers that must keep serving(e.g a DNS server)
+/// can be checked by Hackattic without spawning threads.
+pub trait AsyncHackatticChallenge: 'static {
+    type Problem: serde::de::DeserializeOwned + Debug + Clone + 'static;
+    type Solution: serde::Serialize + Debug + 'static;
+    fn make_solution(problem: Self::Problem, handle: &Handle) -> BoxFuture<Self::Solution>;
+    fn challenge_name() -> &'static str;
+    /// See `HackatticChallenge::verify`
+    fn verify(_problem: &Self::Problem, _solution: &Self::Solution) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Fetches the problem JSON as-is, so it can also be recorded
+    fn get_problem_json(client: &Client, handle: &Handle) -> BoxFuture<Value> {
+        let config = match Config::current() {
+            Ok(config) => config,
+            Err(e) => return Box::new(future::err(e))
+        };
+        let url = match config.authenticated_url(Self::challenge_name(), "problem") {
+            Ok(url) => url,
+            Err(e) => return Box::new(future::err(e))
+        };
+        let client = client.clone();
+        config.retry.retry_async("fetching the problem", handle, move || {
+            Box::new(client.get(&url).send()
+                .and_then(|response| response.error_for_status())
+                .and_then(|mut response| response.json())
+                .map_err(Error::from))
+        })
+    }
+
+    fn send_solution(solution: &Self::Solution, client: &Client, handle: &Handle) -> BoxFuture<SubmissionResult> {
+        let config = match Config::current() {
+            Ok(config) => config,
+            Err(e) => return Box::new(future::err(e))
+        };
+        let url = match config.authenticated_url(Self::challenge_name(), "solve") {
+            Ok(url) => url,
+            Err(e) => return Box::new(future::err(e))
+        };
+        let solution = match serde_json::to_value(solution) {
+            Ok(solution) => solution,
+            Err(e) => return Box::new(future::err(e.into()))
+        };
+        let client = client.clone();
+        let retryable = if config.playground { retry::is_transient } else { retry::is_resubmittable };
+        let policy = config.retry.clone();
+        policy.retry_async_if("submitting the solution", handle, retryable, move || {
+            let playground = config.playground;
+            Box::new(client.post(&url).json(&solution).send()
+                .map_err(Error::from)
+                .and_then(move |response| {
+                    let status = response.status().as_u16();
+                    // kept as text like the sync path does, `SubmissionResult` parses what it can of it
+                    response.into_body()
+                        .fold(Vec::new(), |mut body, chunk| {
+                            body.extend_from_slice(&chunk);
+                            Ok::<_, ::reqwest::Error>(body)
+                        })
+                        .map_err(Error::from)
+                        .and_then(move |body| {
+                            let raw = String::from_utf8_lossy(&body).into_owned();
+                            if status >= 500 {
+                                return Err(ServerError { status, body: raw }.into());
+                            }
+                            Ok(SubmissionResult::from_response(status, raw, playground))
+                        })
+                }))
+        })
+    }
+
+    /// Fetches, solves and submits a problem on the event loop of `handle`, see
+    /// `HackatticChallenge::process_challenge`.
+    fn process_challenge_async(handle: &Handle) -> BoxFuture<Option<SubmissionResult>> {
+        info!("processing challenge \"{}\"", Self::challenge_name());
+        let config = match Config::current() {
+            Ok(config) => config,
+            Err(e) => return Box::new(future::err(e))
+        };
+        let handle = handle.clone();
+        if let Some(ref path) = config.replay {
+            let fixture = match Fixture::load(path).and_then(|fixture| fixture.check_challenge(Self::challenge_name()).map(|_| fixture)) {
+                Ok(fixture) => fixture,
+                Err(e) => return Box::new(future::err(e))
+            };
+            let path = path.clone();
+            let force = config.force;
+            return Box::new(future::result(error::deserialize_problem::<Self::Problem>(fixture.problem.clone()))
+                .and_then(move |problem| Self::make_solution(problem.clone(), &handle).map(move |solution| (problem, solution)))
+                .and_then(move |(problem, solution)| {
+                    check_verification(Self::challenge_name(), Self::verify(&problem, &solution), force)?;
+                    fixture.check_solution(&solution)?;
+                    info!("replayed {}, got solution: {:?}", path.display(), solution);
+                    Ok(None)
+                }));
+        }
+
+        let client = match config.proxy.async_client(&handle) {
+            Ok(client) => client,
+            Err(e) => return Box::new(future::err(e))
+        };
+        let submit_client = client.clone();
+        let submit_handle = handle.clone();
+        let deadline = config.solve_deadline();
+        let run = ChallengeRun::new(Self::challenge_name(), config);
+        // the whole run is polled on this thread, so each phase's span is held until the phase completes
+        let fetch_phase = logging::span("fetch");
+        let fetch_started = Instant::now();
+        Box::new(Self::get_problem_json(&client, &handle)
+            .then(move |problem_json| -> Result<_, Error> {
+                drop(fetch_phase);
+                let mut run = run;
+                let problem_json = run.fetched(problem_json, fetch_started.elapsed())?;
+                Ok((run, problem_json))
+            })
+            .and_then(move |(mut run, problem_json)| {
+                let solve_phase = logging::span("solve");
+                let solve_started = Instant::now();
+                future::result(error::deserialize_problem::<Self::Problem>(problem_json))
+                    .and_then(move |problem| {
+                        debug!("got problem: {:?}", problem);
+                        let solve = Self::make_solution(problem.clone(), &handle);
+                        with_deadline(Self::challenge_name(), solve, deadline, &handle)
+                            .map(move |solution| (problem, solution))
+                    })
+                    .then(move |solved| -> Result<_, Error> {
+                        let solution = run.solved(solved, solve_started.elapsed(), Self::verify);
+                        drop(solve_phase);
+                        Ok((run, solution?))
+                    })
+            })
+            .and_then(move |(run, solution)| {
+                let submit_phase = logging::span("submit");
+                let submit_started = Instant::now();
+                Self::send_solution(&solution, &submit_client, &submit_handle)
+                    .then(move |response| {
+                        drop(submit_phase);
+                        run.submitted(response, submit_started.elapsed())
+                    })
+            }))
+    }
+
+    fn process_challenge() -> Result<Option<SubmissionResult>, Error> {
+        // everything runs on this thread, so the span covers the whole run
+        let _span = logging::span(Self::challenge_name());
+        let mut core = Core::new()?;
+        let handle = core.handle();
+        core.run(Self::process_challenge_async(&handle))
+    }
+}
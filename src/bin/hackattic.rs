@@ -0,0 +1,187 @@
+extern crate hackattic;
+extern crate failure;
+#[macro_use]
+extern crate log;
+extern crate serde_json;
+
+use hackattic::{logging, Config, Registry, Runner};
+use hackattic::error::{EXIT_OTHER, EXIT_USAGE};
+use hackattic::runner::{Summary, DEFAULT_CONCURRENCY};
+use failure::Error;
+use std::fs::File;
+
+const USAGE: &'static str = "Usage:
+    hackattic [options] list                 lists every available challenge
+    hackattic [options] run <challenge>...   runs the given challenges
+    hackattic [options] run --all            runs every available challenge
+
+Run options:
+    --jobs <n>           runs up to <n> challenges at the same time, defaults to 4. serving_dns and
+                         mini_miner always run on their own
+    --json <path>        also writes the summary as JSON to <path>
+
+Options:
+    --config <path>      JSON config file, defaults to $HACKATTIC_CONFIG or ./hackattic.json
+    --base-url <url>     Hackattic API base URL, defaults to $HACKATTIC_BASE_URL or https://hackattic.com
+    --record <dir>       saves each run's problem, solution and response as a fixture under <dir>
+    --replay <fixture>   solves a recorded problem instead of fetching one, without submitting
+    --playground         submits in playground mode, which doesn't use up an attempt
+    --log-format <fmt>   text(the default) or json, one object per line
+    --log-level <level>  e.g debug, ignored when $RUST_LOG is set
+    --float-format <fmt> reports single precision floats as widened(the default), shortest, or hex_float or bits for debugging
+    --force              submits solutions even if they fail their challenge's own verification
+    --profile <name>     uses the access token of <name> from ~/.hackattic/credentials.json
+    --deadline <ms>      fails instead of submitting when solving takes longer than <ms>
+    --proxy <url>        sends HTTP and HTTPS requests through <url>, e.g http://127.0.0.1:8888 for Fiddler
+    --ca-cert <path>     also trusts the PEM or DER certificate at <path>, e.g of an intercepting proxy
+    --max-attempts <n>   attempts per request to Hackattic before giving up, retrying network and 5xx errors
+
+Exit codes:
+    0 every challenge succeeded, 1 other errors, 2 usage or config errors(incl. submissions refused
+    e.g for a bad access token), 3 network errors(incl. rate limited submissions and responses that
+    couldn't be understood), 4 problems that couldn't be deserialized, 5 solver errors, 6 external
+    tool(tesseract, fastcoll, psql...) errors, 7 submissions that were judged wrong";
+
+fn main() {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    match load_config(&mut args) {
+        Ok(config) => {
+            logging::init(&config);
+            Config::set_current(config);
+        },
+        Err(e) => {
+            eprintln!("couldn't load config: {}", e);
+            std::process::exit(EXIT_USAGE);
+        }
+    }
+    let registry = Registry::with_all_challenges();
+    let exit_code = match args.first().map(|arg| arg.as_str()) {
+        Some("list") => {
+            for entry in registry.entries() {
+                println!("{}", entry.name);
+            }
+            0
+        },
+        Some("run") => {
+            let options = take_flag(&mut args, "--jobs").and_then(|jobs| {
+                let jobs = match jobs {
+                    Some(jobs) => jobs.parse().map_err(|_| failure::err_msg(format!("invalid --jobs \"{}\"", jobs)))?,
+                    None => DEFAULT_CONCURRENCY
+                };
+                Ok((jobs, take_flag(&mut args, "--json")?))
+            });
+            let names = if take_switch(&mut args, "--all") {
+                registry.entries().iter().map(|entry| entry.name.to_owned()).collect()
+            } else {
+                args[1..].to_vec()
+            };
+            match options {
+                Ok(_) if names.is_empty() => {
+                    eprintln!("{}", USAGE);
+                    EXIT_USAGE
+                },
+                Ok((jobs, json)) => run_challenges(&registry, &names, jobs, json).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    EXIT_OTHER
+                }),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    EXIT_USAGE
+                }
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            EXIT_USAGE
+        }
+    };
+    std::process::exit(exit_code);
+}
+
+/// Removes `flag` and the value following it from `args`
+fn take_flag(args: &mut Vec<String>, flag: &str) -> Result<Option<String>, Error> {
+    match args.iter().position(|arg| arg == flag) {
+        Some(index) if index + 1 < args.len() => {
+            let value = args.remove(index + 1);
+            args.remove(index);
+            Ok(Some(value))
+        },
+        Some(_) => Err(failure::err_msg(format!("missing value for {}", flag))),
+        None => Ok(None)
+    }
+}
+
+/// Removes `switch` from `args`, returning whether it was present
+fn take_switch(args: &mut Vec<String>, switch: &str) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != switch);
+    args.len() != len
+}
+
+fn load_config(args: &mut Vec<String>) -> Result<Config, Error> {
+    let mut config = match take_flag(args, "--config")? {
+        Some(path) => {
+            let mut config = Config::from_file(path)?;
+            config.apply_env();
+            config
+        },
+        None => Config::load()?
+    };
+    if let Some(base_url) = take_flag(args, "--base-url")? {
+        config.base_url = base_url;
+    }
+    if let Some(record_dir) = take_flag(args, "--record")? {
+        config.record_dir = Some(record_dir.into());
+    }
+    if let Some(replay) = take_flag(args, "--replay")? {
+        config.replay = Some(replay.into());
+    }
+    if let Some(profile) = take_flag(args, "--profile")? {
+        config.profile = Some(profile);
+    }
+    if let Some(deadline) = take_flag(args, "--deadline")? {
+        config.solve_deadline_ms = Some(deadline.parse()
+            .map_err(|_| failure::err_msg(format!("invalid --deadline \"{}\", expected milliseconds", deadline)))?);
+    }
+    if let Some(max_attempts) = take_flag(args, "--max-attempts")? {
+        config.retry.max_attempts = max_attempts.parse()
+            .map_err(|_| failure::err_msg(format!("invalid --max-attempts \"{}\"", max_attempts)))?;
+    }
+    if let Some(proxy) = take_flag(args, "--proxy")? {
+        config.proxy.http = Some(proxy.clone());
+        config.proxy.https = Some(proxy);
+    }
+    while let Some(ca_cert) = take_flag(args, "--ca-cert")? {
+        config.proxy.ca_certs.push(ca_cert.into());
+    }
+    if take_switch(args, "--playground") {
+        config.playground = true;
+    }
+    if let Some(log_format) = take_flag(args, "--log-format")? {
+        config.log_format = log_format.parse()?;
+    }
+    if let Some(log_level) = take_flag(args, "--log-level")? {
+        config.log_level = Some(log_level);
+    }
+    if let Some(float_format) = take_flag(args, "--float-format")? {
+        config.float_format = float_format.parse()?;
+    }
+    if take_switch(args, "--force") {
+        config.force = true;
+    }
+    config.proxy.validate()?;
+    Ok(config)
+}
+
+/// Runs the challenges concurrently, continuing past failures and panics, then prints
+/// a summary. Returns the exit code of the first challenge that didn't succeed, or 0.
+fn run_challenges(registry: &Registry, names: &[String], jobs: usize, json: Option<String>) -> Result<i32, Error> {
+    let reports = Runner::new(registry).concurrency(jobs).run(names);
+    println!("{}", Summary(&reports));
+    if let Some(json) = json {
+        let file = File::create(&json)?;
+        serde_json::to_writer_pretty(file, &reports)?;
+        info!("wrote JSON report to {}", json);
+    }
+    Ok(reports.iter().map(|report| report.exit_code).find(|&code| code != 0).unwrap_or(0))
+}
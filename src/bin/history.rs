@@ -0,0 +1,45 @@
+extern crate hackattic;
+extern crate failure;
+
+use hackattic::Config;
+use hackattic::history;
+use hackattic::timing::format_duration;
+use failure::Error;
+use std::path::PathBuf;
+
+const USAGE: &'static str = "Usage:
+    history [<history file>]   shows the success rate and median solve time of every challenge
+
+The history file defaults to the configured one, ~/.hackattic/history.jsonl unless overridden
+by $HACKATTIC_HISTORY or \"history_file\" in the config file.";
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") || args.len() > 1 {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+    if let Err(e) = show_history(args.first().map(PathBuf::from)) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn show_history(path: Option<PathBuf>) -> Result<(), Error> {
+    let path = match path {
+        Some(path) => path,
+        None => Config::load()?.history_file
+            .ok_or_else(|| failure::err_msg("history is disabled in the config, pass a history file explicitly"))?
+    };
+    let runs = history::load(&path)?;
+    println!("{:<24} {:>6} {:>9} {:>9} {:>14}", "challenge", "runs", "accepted", "success", "median solve");
+    for stats in history::stats(&runs) {
+        println!("{:<24} {:>6} {:>9} {:>8.1}% {:>14}",
+                 stats.challenge,
+                 stats.runs,
+                 stats.accepted,
+                 stats.success_rate() * 100.0,
+                 stats.median_solve.map_or("-".to_owned(), format_duration));
+    }
+    Ok(())
+}
@@ -0,0 +1,17 @@
+extern crate hackattic;
+
+use hackattic::{logging, Config};
+use hackattic::mock_server::MockServer;
+
+/// Serves mock problems until killed, e.g:
+///     mock_server 127.0.0.1:8080
+///     hackattic --base-url http://127.0.0.1:8080 run mini_miner
+fn main() {
+    logging::init(&Config::load().unwrap_or_default());
+    let addr = std::env::args().nth(1).unwrap_or("127.0.0.1:8080".to_owned());
+    let server = MockServer::start(&addr).unwrap();
+    println!("Serving {} on {}", server.challenge_names().join(", "), server.base_url());
+    loop {
+        std::thread::park();
+    }
+}
@@ -1,5 +0,0 @@
-extern crate hackattic;
-
-fn main() {
-    hackattic::visual_basic_math::main();
-}
\ No newline at end of file
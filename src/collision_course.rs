@@ -0,0 +1,60 @@
+use super::Base64;
+use super::error::run_tool;
+use tempfile;
+use failure::{Error, ResultExt};
+use std::io::{Read, Write};
+use openssl::hash::{hash, MessageDigest};
+
+#[derive(Deserialize, Debug, Clone)]
+struct Problem {
+    include: String
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Solution {
+    files: Vec<Base64>
+}
+
+fn create_collision(mut include: &[u8]) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let mut prefix_file = tempfile::NamedTempFile::new()?;
+    prefix_file.write_all(&mut include)?;
+    run_tool("fastcoll", ::std::process::Command::new("extra/collision_course/fastcoll_v1.0.0.5.exe")
+        .arg("-p")
+        .arg(prefix_file.path()), None)?;
+    let mut msg1 = ::std::fs::File::open("msg1.bin")?;
+    let mut msg2 = ::std::fs::File::open("msg2.bin")?;
+    let mut file0 = Vec::new();
+    let mut file1 = Vec::new();
+    msg1.read_to_end(&mut file0)?;
+    msg2.read_to_end(&mut file1)?;
+    ::std::fs::remove_file("msg1.bin")?;
+    ::std::fs::remove_file("msg2.bin")?;
+    Ok((file0, file1))
+}
+
+
+#[hackattic_challenge(name = "collision_course", verify = "verify_solution")]
+fn solve(req: &Problem) -> Result<Solution, Error> {
+    let (file0, file1) = create_collision(req.include.as_bytes())?;
+    Ok(Solution {
+        files: vec![Base64::from(file0), Base64::from(file1)]
+    })
+}
+
+fn verify_solution(req: &Problem, solution: &Solution) -> Result<(), Error> {
+    let (file0, file1) = match solution.files.as_slice() {
+        &[ref file0, ref file1] => (&file0.0, &file1.0),
+        files => bail!("expected 2 files, got {}", files.len())
+    };
+    if file0 == file1 {
+        bail!("both files are identical");
+    }
+    if !file0.starts_with(req.include.as_bytes()) || !file1.starts_with(req.include.as_bytes()) {
+        bail!("files don't start with the included string");
+    }
+    let md5 = |file: &[u8]| hash(MessageDigest::md5(), file);
+    if md5(file0)? != md5(file1)? {
+        bail!("files have different MD5 hashes");
+    }
+    Ok(())
+}
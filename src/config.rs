@@ -0,0 +1,272 @@
+use failure::{Error, ResultExt};
+use serde_json;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::cell::RefCell;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use super::assets::AssetConfig;
+use super::credentials;
+use super::floats::FloatFormat;
+use super::logging::LogFormat;
+use super::proxy::ProxyConfig;
+use super::retry::RetryPolicy;
+
+pub const DEFAULT_BASE_URL: &'static str = "https://hackattic.com";
+/// Config file used when `HACKATTIC_CONFIG` isn't set, only read if it exists.
+pub const DEFAULT_CONFIG_FILE: &'static str = "hackattic.json";
+
+lazy_static! {
+    static ref CURRENT: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+}
+
+thread_local! {
+    // set by `Config::scoped`, takes priority over `CURRENT`
+    static SCOPED: RefCell<Option<Arc<Config>>> = RefCell::new(None);
+}
+
+/// Framework-wide settings. Values are taken from (in increasing priority)
+/// the defaults, a JSON config file, environment variables and finally whatever
+/// the caller(usually the `hackattic` binary's flags) sets explicitly.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Scheme and host of the Hackattic API, e.g a local mock server
+    pub base_url: String,
+    /// When set, every run saves its problem, solution and response as a fixture here
+    pub record_dir: Option<PathBuf>,
+    /// When set, the problem is loaded from this fixture instead of being fetched,
+    /// and nothing is submitted
+    pub replay: Option<PathBuf>,
+    /// Submit with `playground=1`, which doesn't use up an attempt and returns diagnostics
+    pub playground: bool,
+    /// See `credentials::access_token` for how these are used
+    pub access_token: Option<String>,
+    pub profile: Option<String>,
+    pub credentials_file: Option<PathBuf>,
+    /// How long solving may take before the problem is considered expired and
+    /// isn't submitted. No deadline when unset.
+    pub solve_deadline_ms: Option<u64>,
+    /// Applies to fetching problems and submitting solutions
+    pub retry: RetryPolicy,
+    /// Applies to every HTTP client the crate builds
+    pub proxy: ProxyConfig,
+    /// See `assets::fetch_asset`
+    pub assets: AssetConfig,
+    /// JSONL file every run is appended to, see `history`. Disabled when unset. Loaded configs
+    /// default to `~/.hackattic/history.jsonl`, but `Config::default()`(e.g in tests) to none.
+    #[serde(default = "default_history_file")]
+    pub history_file: Option<PathBuf>,
+    /// Submit even if a solution fails its challenge's own `verify`
+    pub force: bool,
+    pub log_format: LogFormat,
+    /// e.g "debug", applying to this crate's logs. Overridden by `RUST_LOG`.
+    pub log_level: Option<String>,
+    /// How single precision floats are reported in solutions, see `floats`
+    pub float_format: FloatFormat
+}
+
+/// `~/.hackattic`, holding per-user files such as credentials and the run history
+pub fn hackattic_dir() -> PathBuf {
+    let home = ::std::env::var("HOME").or_else(|_| ::std::env::var("USERPROFILE")).unwrap_or_default();
+    PathBuf::from(home).join(".hackattic")
+}
+
+fn default_history_file() -> Option<PathBuf> {
+    Some(hackattic_dir().join("history.jsonl"))
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            record_dir: None,
+            replay: None,
+            playground: false,
+            access_token: None,
+            profile: None,
+            credentials_file: None,
+            solve_deadline_ms: None,
+            retry: RetryPolicy::default(),
+            proxy: ProxyConfig::default(),
+            assets: AssetConfig::default(),
+            history_file: None,
+            force: false,
+            log_format: LogFormat::default(),
+            log_level: None,
+            float_format: FloatFormat::default()
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config file pointed by `HACKATTIC_CONFIG`(or `hackattic.json`
+    /// if present), then applies environment variable overrides.
+    pub fn load() -> Result<Config, Error> {
+        let mut config = match ::std::env::var("HACKATTIC_CONFIG") {
+            Ok(path) => Config::from_file(path)?,
+            Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => Config::from_file(DEFAULT_CONFIG_FILE)?,
+            Err(_) => Config { history_file: default_history_file(), ..Config::default() }
+        };
+        config.apply_env();
+        config.proxy.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Config, Error> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|_| format!("couldn't open config file {}", path.display()))?;
+        let config = serde_json::from_reader(file)
+            .with_context(|_| format!("couldn't parse config file {}", path.display()))?;
+        Ok(config)
+    }
+
+    pub fn apply_env(&mut self) {
+        if let Ok(base_url) = ::std::env::var("HACKATTIC_BASE_URL") {
+            self.base_url = base_url;
+        }
+        if let Ok(record_dir) = ::std::env::var("HACKATTIC_RECORD_DIR") {
+            self.record_dir = Some(PathBuf::from(record_dir));
+        }
+        if let Ok(playground) = ::std::env::var("HACKATTIC_PLAYGROUND") {
+            self.playground = playground == "1" || playground == "true";
+        }
+        if let Some(log_format) = ::std::env::var("HACKATTIC_LOG_FORMAT").ok().and_then(|format| format.parse().ok()) {
+            self.log_format = log_format;
+        }
+        if let Some(float_format) = ::std::env::var("HACKATTIC_FLOAT_FORMAT").ok().and_then(|format| format.parse().ok()) {
+            self.float_format = float_format;
+        }
+        if let Ok(force) = ::std::env::var("HACKATTIC_FORCE") {
+            self.force = force == "1" || force == "true";
+        }
+        if let Ok(profile) = ::std::env::var("HACKATTIC_PROFILE") {
+            self.profile = Some(profile);
+        }
+        self.proxy.apply_env();
+        if let Ok(history_file) = ::std::env::var("HACKATTIC_HISTORY") {
+            self.history_file = if history_file.is_empty() { None } else { Some(PathBuf::from(history_file)) };
+        }
+        if let Ok(cache_dir) = ::std::env::var("HACKATTIC_ASSET_CACHE") {
+            self.assets.cache_dir = if cache_dir.is_empty() { None } else { Some(PathBuf::from(cache_dir)) };
+        }
+        if let Some(max_attempts) = ::std::env::var("HACKATTIC_MAX_ATTEMPTS").ok().and_then(|n| n.parse().ok()) {
+            self.retry.max_attempts = max_attempts;
+        }
+        if let Some(deadline) = ::std::env::var("HACKATTIC_SOLVE_DEADLINE_MS").ok().and_then(|ms| ms.parse().ok()) {
+            self.solve_deadline_ms = Some(deadline);
+        }
+    }
+
+    pub fn solve_deadline(&self) -> Option<Duration> {
+        self.solve_deadline_ms.map(Duration::from_millis)
+    }
+
+    /// URL of a challenge endpoint, e.g `problem` or `solve`
+    pub fn challenge_url(&self, challenge_name: &str, endpoint: &str) -> String {
+        format!("{}/challenges/{}/{}", self.base_url.trim_right_matches('/'), challenge_name, endpoint)
+    }
+
+    /// URL of a websocket endpoint on the same host, e.g "wss://hackattic.com/_/ws/<token>"
+    /// for the path "/_/ws/<token>"
+    pub fn websocket_url(&self, path: &str) -> Result<String, Error> {
+        let base_url = self.base_url.trim_right_matches('/');
+        let (scheme, host) = if base_url.starts_with("https://") {
+            ("wss", &base_url["https://".len()..])
+        } else if base_url.starts_with("http://") {
+            ("ws", &base_url["http://".len()..])
+        } else {
+            bail!("base URL {} isn't an http:// or https:// URL", self.base_url);
+        };
+        Ok(format!("{}://{}/{}", scheme, host, path.trim_left_matches('/')))
+    }
+
+    /// `challenge_url` with the access token(and the playground flag when solving) as query parameters
+    pub fn authenticated_url(&self, challenge_name: &str, endpoint: &str) -> Result<String, Error> {
+        let mut url = format!("{}?access_token={}", self.challenge_url(challenge_name, endpoint), credentials::access_token(self)?);
+        if endpoint == "solve" && self.playground {
+            url.push_str("&playground=1");
+        }
+        Ok(url)
+    }
+
+    /// The config used by the framework: the one installed on this thread by `Config::scoped`,
+    /// or else the one loaded via `Config::load` on first use, unless one was already installed
+    /// by `Config::set_current`.
+    pub fn current() -> Result<Arc<Config>, Error> {
+        if let Some(config) = SCOPED.with(|scoped| scoped.borrow().clone()) {
+            return Ok(config);
+        }
+        if let Some(ref config) = *CURRENT.read().unwrap() {
+            return Ok(config.clone());
+        }
+        let mut current = CURRENT.write().unwrap();
+        if current.is_none() {
+            *current = Some(Arc::new(Config::load()?));
+        }
+        Ok(current.as_ref().unwrap().clone())
+    }
+
+    pub fn set_current(config: Config) {
+        *CURRENT.write().unwrap() = Some(Arc::new(config));
+    }
+
+    /// Makes `config` the current one on this thread only, until the returned guard is
+    /// dropped. E.g a test running challenges against a mock server uses it so that other
+    /// tests, running on other threads, still see the usual config.
+    pub fn scoped<C: Into<Arc<Config>>>(config: C) -> ScopedConfig {
+        let previous = SCOPED.with(|scoped| scoped.borrow_mut().replace(config.into()));
+        ScopedConfig { previous }
+    }
+}
+
+/// Restores the previous config of the thread when dropped, see `Config::scoped`
+pub struct ScopedConfig {
+    previous: Option<Arc<Config>>
+}
+
+impl Drop for ScopedConfig {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SCOPED.with(|scoped| *scoped.borrow_mut() = previous);
+    }
+}
+
+#[test]
+fn can_read_config_file() {
+    use std::io::Write;
+    let mut file = ::tempfile::NamedTempFile::new().unwrap();
+    file.write_all(br#"{ "base_url": "http://127.0.0.1:8080/" }"#).unwrap();
+    let config = Config::from_file(file.path()).unwrap();
+    assert_eq!(config.challenge_url("mini_miner", "problem"), "http://127.0.0.1:8080/challenges/mini_miner/problem");
+}
+
+#[test]
+fn builds_websocket_urls() {
+    let config = Config::default();
+    assert_eq!(config.websocket_url("/_/ws/abc").unwrap(), "wss://hackattic.com/_/ws/abc");
+    let config = Config { base_url: "http://127.0.0.1:8080/".to_owned(), ..Config::default() };
+    assert_eq!(config.websocket_url("/_/ws/abc").unwrap(), "ws://127.0.0.1:8080/_/ws/abc");
+}
+
+#[test]
+fn missing_fields_use_defaults() {
+    let config: Config = serde_json::from_str("{}").unwrap();
+    assert_eq!(config.base_url, DEFAULT_BASE_URL);
+    assert_eq!(config.history_file, default_history_file());
+    assert_eq!(Config::default().history_file, None);
+}
+
+#[test]
+fn scopes_config_to_thread() {
+    let _config = Config::scoped(Config { base_url: "http://127.0.0.1:1".to_owned(), ..Config::default() });
+    assert_eq!(Config::current().unwrap().base_url, "http://127.0.0.1:1");
+    let other_thread = ::std::thread::spawn(|| SCOPED.with(|scoped| scoped.borrow().is_none())).join().unwrap();
+    assert!(other_thread);
+    {
+        let _inner = Config::scoped(Config { base_url: "http://127.0.0.1:2".to_owned(), ..Config::default() });
+        assert_eq!(Config::current().unwrap().base_url, "http://127.0.0.1:2");
+    }
+    assert_eq!(Config::current().unwrap().base_url, "http://127.0.0.1:1");
+}
@@ -0,0 +1,137 @@
+//! Resolution of the Hackattic access token. Tokens can come from the
+//! `HACKATTIC_ACCESS_TOKEN` environment variable, the config file, or a credentials
+//! file holding several named profiles, e.g `{ "alice": "<token>", "bob": "<token>" }`.
+
+use failure::{Error, ResultExt};
+use serde_json;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::PathBuf;
+use super::Config;
+use super::config::hackattic_dir;
+
+pub const DEFAULT_PROFILE: &'static str = "default";
+
+#[derive(Debug, Fail)]
+pub enum CredentialsError {
+    #[fail(display = "no access token found, set HACKATTIC_ACCESS_TOKEN, \"access_token\" in the config file, \
+                      or a \"default\" profile in {}", credentials_file)]
+    Missing { credentials_file: String },
+    #[fail(display = "profile \"{}\" not found in {}", profile, credentials_file)]
+    UnknownProfile { profile: String, credentials_file: String }
+}
+
+/// A source of access tokens. `Ok(None)` means the source has nothing to offer
+/// and the next one should be tried.
+pub trait CredentialsProvider {
+    fn access_token(&self) -> Result<Option<String>, Error>;
+}
+
+pub struct EnvProvider;
+impl CredentialsProvider for EnvProvider {
+    fn access_token(&self) -> Result<Option<String>, Error> {
+        Ok(::std::env::var("HACKATTIC_ACCESS_TOKEN").ok().filter(|token| !token.is_empty()))
+    }
+}
+
+pub struct ConfigProvider<'a>(pub &'a Config);
+impl<'a> CredentialsProvider for ConfigProvider<'a> {
+    fn access_token(&self) -> Result<Option<String>, Error> {
+        Ok(self.0.access_token.clone())
+    }
+}
+
+pub struct ProfileFileProvider {
+    pub path: PathBuf,
+    pub profile: String,
+    /// Whether a missing file or profile is an error rather than "nothing to offer"
+    pub required: bool
+}
+
+impl ProfileFileProvider {
+    pub fn profiles(&self) -> Result<BTreeMap<String, String>, Error> {
+        let file = File::open(&self.path)
+            .with_context(|_| format!("couldn't open credentials file {}", self.path.display()))?;
+        let profiles = serde_json::from_reader(file)
+            .with_context(|_| format!("couldn't parse credentials file {}", self.path.display()))?;
+        Ok(profiles)
+    }
+}
+
+impl CredentialsProvider for ProfileFileProvider {
+    fn access_token(&self) -> Result<Option<String>, Error> {
+        if !self.required && !self.path.exists() {
+            return Ok(None);
+        }
+        match self.profiles()?.remove(&self.profile) {
+            Some(token) => Ok(Some(token)),
+            None if !self.required => Ok(None),
+            None => Err(CredentialsError::UnknownProfile {
+                profile: self.profile.clone(),
+                credentials_file: self.path.display().to_string()
+            }.into())
+        }
+    }
+}
+
+/// `~/.hackattic/credentials.json`, unless overridden by the config
+pub fn credentials_file(config: &Config) -> PathBuf {
+    if let Some(ref path) = config.credentials_file {
+        return path.clone();
+    }
+    hackattic_dir().join("credentials.json")
+}
+
+/// Finds the access token to use. An explicitly selected profile must exist, otherwise
+/// the environment, the config file and the "default" profile are tried in that order.
+pub fn access_token<'a>(config: &'a Config) -> Result<String, Error> {
+    let path = credentials_file(config);
+    let mut providers: Vec<Box<CredentialsProvider + 'a>> = Vec::new();
+    match config.profile {
+        Some(ref profile) => {
+            providers.push(Box::new(ProfileFileProvider { path: path.clone(), profile: profile.clone(), required: true }));
+        },
+        None => {
+            providers.push(Box::new(EnvProvider));
+            providers.push(Box::new(ConfigProvider(config)));
+            providers.push(Box::new(ProfileFileProvider { path: path.clone(), profile: DEFAULT_PROFILE.to_owned(), required: false }));
+        }
+    }
+    for provider in providers {
+        if let Some(token) = provider.access_token()? {
+            return Ok(token);
+        }
+    }
+    Err(CredentialsError::Missing { credentials_file: path.display().to_string() }.into())
+}
+
+#[test]
+fn selects_token_by_profile() {
+    use std::io::Write;
+    let mut file = ::tempfile::NamedTempFile::new().unwrap();
+    file.write_all(br#"{ "alice": "alice-token", "bob": "bob-token" }"#).unwrap();
+    let mut config = Config {
+        credentials_file: Some(file.path().to_owned()),
+        profile: Some("bob".to_owned()),
+        ..Config::default()
+    };
+    assert_eq!(access_token(&config).unwrap(), "bob-token");
+    config.profile = Some("carol".to_owned());
+    let err = access_token(&config).unwrap_err();
+    assert!(err.downcast_ref::<CredentialsError>().is_some());
+}
+
+#[test]
+fn optional_providers_can_be_empty() {
+    let config = Config {
+        access_token: Some("config-token".to_owned()),
+        ..Config::default()
+    };
+    assert_eq!(ConfigProvider(&config).access_token().unwrap(), Some("config-token".to_owned()));
+    let missing_file = ProfileFileProvider {
+        path: PathBuf::from("does/not/exist.json"),
+        profile: DEFAULT_PROFILE.to_owned(),
+        required: false
+    };
+    assert_eq!(missing_file.access_token().unwrap(), None);
+}
@@ -1,37 +1,23 @@
-extern crate hackattic;
-#[macro_use]
-extern crate serde_derive;
-extern crate serde_json;
-extern crate serde;
-#[macro_use]
-extern crate failure;
-extern crate tempfile;
-#[macro_use]
-extern crate log;
-extern crate ansi_term;
-extern crate env_logger;
-extern crate pretty_env_logger;
-extern crate futures;
-extern crate futures_cpupool;
-extern crate trust_dns_server;
-extern crate trust_dns_proto;
-extern crate tokio_core;
-extern crate base64;
-
-
 use std::collections::BTreeMap;
 use std::str::FromStr;
-use std::net::{UdpSocket, Ipv4Addr, Ipv6Addr};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::rc::Rc;
 use std::time::Duration;
-use serde::{Deserialize, Deserializer};
-use hackattic::{HackatticChallenge, make_reqwest_client};
-use failure::{Error, ResultExt};
+use super::{AsyncHackatticChallenge, BoxFuture, Registry};
+use failure::Error;
 use trust_dns_proto::rr::{Record, RecordType, RData, Name};
 use trust_dns_proto::rr::rdata::{TXT, NULL, SOA};
 use trust_dns_proto::serialize::binary::{BinSerializable, BinEncoder};
-use trust_dns_server::ServerFuture;
+use trust_dns_proto::tcp::TcpStream;
+use trust_dns_proto::udp::UdpStream;
 use trust_dns_server::authority::{Authority, Catalog, ZoneType};
-use futures::Future;
+use trust_dns_server::server::{RequestHandler, RequestStream, TimeoutStream};
+use futures::{future, Future, Stream};
+use tokio_core::net::TcpListener;
+use tokio_core::reactor::Handle;
+
+/// Same as the timeout `ServerFuture` was given
+const TCP_TIMEOUT_SECS: u64 = 30;
 
 
 #[derive(Deserialize, Debug, Clone)]
@@ -108,13 +94,6 @@ struct Solution {
     dns_port: u32
 }
 
-fn main() {
-    ansi_term::enable_ansi_support().unwrap();
-    let mut log_builder = pretty_env_logger::init();
-    info!("Logger initialized");
-    DnsServer::process_challenge().unwrap()
-}
-
 fn create_soa(name: Name) -> Result<Record, Error>{
     let _type = RecordType::SOA;
     let rdata = RData::SOA(SOA::new(
@@ -141,41 +120,65 @@ fn records_to_catalog(records: &[Record]) -> Result<Catalog, Error> {
     Ok(catalog)
 }
 
+// `ServerFuture` runs on an event loop of its own, so its sockets are registered the same way
+// here, but on the challenge's loop, which keeps running while the solution is submitted.
+
+/// Serves `catalog` over UDP on the event loop of `handle`
+fn serve_udp(catalog: Rc<Catalog>, socket: ::std::net::UdpSocket, handle: &Handle) {
+    let (buf_stream, stream_handle) = UdpStream::with_bound(socket, handle);
+    let requests = RequestStream::new(buf_stream, stream_handle);
+    handle.spawn(requests
+        .for_each(move |(request, mut response_handle)| response_handle.send(catalog.handle_request(&request)))
+        .map_err(|e| error!("UDP DNS server failed: {}", e)));
+}
+
+/// Serves `catalog` over TCP on the event loop of `handle`. Connections are closed after
+/// `TCP_TIMEOUT` without a query.
+fn serve_tcp(catalog: Rc<Catalog>, listener: ::std::net::TcpListener, handle: &Handle) -> Result<(), Error> {
+    let addr = listener.local_addr()?;
+    let listener = TcpListener::from_listener(listener, &addr, handle)?;
+    let connection_handle = handle.clone();
+    let server = listener.incoming().for_each(move |(tcp_stream, src_addr)| {
+        let (buf_stream, stream_handle) = TcpStream::from_stream(tcp_stream, src_addr);
+        let timeout_stream = TimeoutStream::new(buf_stream, Duration::from_secs(TCP_TIMEOUT_SECS), &connection_handle)?;
+        let catalog = catalog.clone();
+        connection_handle.spawn(RequestStream::new(timeout_stream, stream_handle)
+            .for_each(move |(request, mut response_handle)| response_handle.send(catalog.handle_request(&request)))
+            .map_err(move |e| warn!("TCP DNS connection from {} failed: {}", src_addr, e)));
+        Ok(())
+    }).map_err(|e| error!("TCP DNS server failed: {}", e));
+    handle.spawn(server);
+    Ok(())
+}
+
+pub fn register(registry: &mut Registry) {
+    registry.register_async::<DnsServer>();
+    // serves on a fixed port, so it has to run on its own
+    registry.set_exclusive(DnsServer::challenge_name());
+}
+
 struct DnsServer;
-impl HackatticChallenge for DnsServer {
+impl AsyncHackatticChallenge for DnsServer {
     type Problem = Problem;
     type Solution = Solution;
     fn challenge_name() -> &'static str {
         "serving_dns"
     }
-    fn make_solution(req: &Problem) -> Result<Solution, Error> {
-        let mut records = req.records.iter().map(ProblemRecord::as_record).collect::<Result<Vec<_>,_>>()?;
-        let soa = create_soa(records[0].name().clone())?;
-        records.push(soa);
-        let catalog = records_to_catalog(&records)?;
-        let mut server = ServerFuture::new(catalog)?;
-        let udp = std::net::UdpSocket::bind("0.0.0.0:13801")?;
-        let tcp = std::net::TcpListener::bind("0.0.0.0:13801")?;
-        server.register_socket(udp);
-        server.register_listener(tcp, Duration::from_secs(30));
-        let solution = Solution {
-            dns_port: 13801,
-            dns_ip: "37.142.197.254".to_owned()
+    /// Starts serving on the challenge's event loop, which keeps running while the solution
+    /// is submitted, so Hackattic can query us before responding.
+    fn make_solution(req: Problem, handle: &Handle) -> BoxFuture<Solution> {
+        let start = || -> Result<Solution, Error> {
+            let mut records = req.records.iter().map(ProblemRecord::as_record).collect::<Result<Vec<_>,_>>()?;
+            let soa = create_soa(records[0].name().clone())?;
+            records.push(soa);
+            let catalog = Rc::new(records_to_catalog(&records)?);
+            serve_udp(catalog.clone(), ::std::net::UdpSocket::bind("0.0.0.0:13801")?, handle);
+            serve_tcp(catalog, ::std::net::TcpListener::bind("0.0.0.0:13801")?, handle)?;
+            Ok(Solution {
+                dns_port: 13801,
+                dns_ip: "37.142.197.254".to_owned()
+            })
         };
-        let mut core = server.tokio_core();
-        let mut client = make_reqwest_client()?;
-
-        core.handle().spawn_fn(move || {
-            std::thread::spawn(move || {
-                let res = DnsServer::send_solution(&solution, &mut client).unwrap();
-                error!("Got response {:?}", res);
-            });
-            Ok(())
-        });
-
-        loop {
-            core.turn(None);
-        }
-        unreachable!("never end")
+        Box::new(future::result(start()))
     }
 }
@@ -0,0 +1,155 @@
+//! The categories a failed run falls into, each mapped to a process exit code
+//! so that scripts running the solvers can react to them.
+
+use failure::Error;
+use reqwest;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_path_to_error;
+use std::io::{self, Write};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use super::retry::ServerError;
+
+pub const EXIT_OTHER: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NETWORK: i32 = 3;
+pub const EXIT_DESERIALIZE: i32 = 4;
+pub const EXIT_SOLVER: i32 = 5;
+pub const EXIT_EXTERNAL_TOOL: i32 = 6;
+pub const EXIT_REJECTED: i32 = 7;
+
+#[derive(Debug, Fail)]
+pub enum HackatticError {
+    #[fail(display = "network error while {}: {}", action, message)]
+    Network { action: String, message: String },
+    #[fail(display = "couldn't deserialize the problem at `{}`: {}", path, message)]
+    Deserialize { path: String, message: String },
+    #[fail(display = "solver failed: {}", message)]
+    Solver { message: String },
+    #[fail(display = "{} failed: {}", tool, message)]
+    ExternalTool { tool: String, message: String },
+    #[fail(display = "submission wasn't accepted: {}", reason)]
+    Rejected { reason: String },
+    /// The submission wasn't judged, e.g because of a bad access token
+    #[fail(display = "submission was refused (HTTP {}): {}", status, reason)]
+    Refused { status: u16, reason: String },
+    #[fail(display = "submission was rate limited (HTTP {})", status)]
+    RateLimited { status: u16 },
+    #[fail(display = "couldn't understand the submission response (HTTP {}): {}", status, body)]
+    MalformedResponse { status: u16, body: String }
+}
+
+impl HackatticError {
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            HackatticError::Network { .. } => EXIT_NETWORK,
+            HackatticError::Deserialize { .. } => EXIT_DESERIALIZE,
+            HackatticError::Solver { .. } => EXIT_SOLVER,
+            HackatticError::ExternalTool { .. } => EXIT_EXTERNAL_TOOL,
+            HackatticError::Rejected { .. } => EXIT_REJECTED,
+            HackatticError::Refused { .. } => EXIT_USAGE,
+            HackatticError::RateLimited { .. } | HackatticError::MalformedResponse { .. } => EXIT_NETWORK
+        }
+    }
+
+    /// Categorizes `error` as a network error if it came from the connection or server,
+    /// other errors(e.g missing credentials) are returned as they are.
+    pub fn network(action: &str, error: Error) -> Error {
+        let is_network = error.downcast_ref::<reqwest::Error>().is_some()
+            || error.downcast_ref::<ServerError>().is_some()
+            || error.downcast_ref::<io::Error>().is_some();
+        if !is_network {
+            return error;
+        }
+        HackatticError::Network { action: action.to_owned(), message: error.to_string() }.into()
+    }
+
+    /// Categorizes an error returned while solving, unless it already has a category
+    /// (e.g an external tool failing).
+    pub fn solver(error: Error) -> Error {
+        if error.downcast_ref::<HackatticError>().is_some() {
+            return error;
+        }
+        HackatticError::Solver { message: error.to_string() }.into()
+    }
+
+    pub fn external_tool<M: ToString>(tool: &str, message: M) -> Error {
+        HackatticError::ExternalTool { tool: tool.to_owned(), message: message.to_string() }.into()
+    }
+}
+
+pub fn exit_code(error: &Error) -> i32 {
+    error.downcast_ref::<HackatticError>().map_or(EXIT_OTHER, HackatticError::exit_code)
+}
+
+/// Like `serde_json::from_value`, but the error tells which field of the problem was wrong.
+pub fn deserialize_problem<T: DeserializeOwned>(json: Value) -> Result<T, Error> {
+    serde_path_to_error::deserialize(json).map_err(|e| {
+        HackatticError::Deserialize { path: e.path().to_string(), message: e.inner().to_string() }.into()
+    })
+}
+
+/// Runs an external tool to completion, feeding it `input`. Failing to start it or
+/// a non-zero exit status are reported as `HackatticError::ExternalTool`.
+pub fn run_tool(tool: &str, command: &mut Command, input: Option<&[u8]>) -> Result<Output, Error> {
+    let mut child = command
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| HackatticError::external_tool(tool, format!("couldn't start it: {}", e)))?;
+    // the input is written from another thread while the output is read, otherwise a tool that
+    // fills its stdout before it's done reading(e.g psql restoring a large dump) would deadlock
+    let writer = input.map(|input| {
+        let mut stdin = child.stdin.take().unwrap();
+        let input = input.to_owned();
+        thread::spawn(move || stdin.write_all(&input))
+    });
+    let output = child.wait_with_output().map_err(|e| HackatticError::external_tool(tool, e))?;
+    if !output.status.success() {
+        return Err(HackatticError::external_tool(tool, format!("{}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    if let Some(writer) = writer {
+        writer.join().expect("stdin writer panicked")
+            .map_err(|e| HackatticError::external_tool(tool, format!("couldn't write its input: {}", e)))?;
+    }
+    Ok(output)
+}
+
+#[test]
+fn reports_problem_json_path() {
+    #[derive(Deserialize, Debug)]
+    struct Block {
+        #[allow(dead_code)]
+        nonce: Option<u64>
+    }
+    #[derive(Deserialize, Debug)]
+    struct Problem {
+        #[allow(dead_code)]
+        block: Block
+    }
+    let err = deserialize_problem::<Problem>(json!({ "block": { "nonce": "zero" } })).unwrap_err();
+    assert_eq!(exit_code(&err), EXIT_DESERIALIZE);
+    match err.downcast_ref::<HackatticError>() {
+        Some(&HackatticError::Deserialize { ref path, .. }) => assert_eq!(path, "block.nonce"),
+        other => panic!("unexpected error {:?}", other)
+    }
+}
+
+#[test]
+fn keeps_existing_categories() {
+    let tool_error = HackatticError::external_tool("tesseract", "not found");
+    assert_eq!(exit_code(&HackatticError::solver(tool_error)), EXIT_EXTERNAL_TOOL);
+    assert_eq!(exit_code(&HackatticError::solver(format_err!("no nonce found"))), EXIT_SOLVER);
+    assert_eq!(exit_code(&HackatticError::network("fetching the problem", format_err!("no access token"))), EXIT_OTHER);
+}
+
+#[cfg(unix)]
+#[test]
+fn feeds_large_input_while_reading_output() {
+    // more than a pipe buffer each way, so writing all the input first would block forever
+    let input = vec![b'x'; 4 * 1024 * 1024];
+    let output = run_tool("cat", &mut Command::new("cat"), Some(&input)).unwrap();
+    assert_eq!(output.stdout.len(), input.len());
+}
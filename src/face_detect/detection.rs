@@ -5,7 +5,7 @@ use failure::Error;
 pub fn detect_faces(image: &[u8]) -> Result<Vec<Rect>, Error> {
     let classifier = CascadeClassifier::from_path("extra/face_detect/haarcascade_frontalface_default.xml")
         .expect("Couldn't load face detection cascade");
-    println!("Loading image of len {}", image.len());
+    debug!("Loading image of len {}", image.len());
     let image = Mat::imdecode(image, imgcodecs::ImreadModes::ImreadGrayscale);
     let faces = classifier.detect_with_params(&image,
         1.1,
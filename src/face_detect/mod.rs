@@ -1,9 +1,7 @@
-#![cfg(facedetect)]
+#![cfg(feature = "facedetect")]
 
-use super::HackatticChallenge;
-use reqwest;
+use super::fetch_asset;
 use failure::Error;
-use std::io::Read;
 use cv::Rect;
 
 pub mod detection;
@@ -25,23 +23,11 @@ fn face_rect_to_usize(rect: &Rect) -> (usize, usize) {
     (row,col)
 }
 
-pub struct FaceDetection;
-impl HackatticChallenge for FaceDetection {
-    type Problem = Problem;
-    type Solution = Solution;
-
-    fn make_solution(problem: &Self::Problem) -> Result<Self::Solution, Error> {
-        let image_buf = {
-            let mut res = reqwest::get(&problem.image_url)?;
-            let mut buf = Vec::new();
-            res.read_to_end(&mut buf)?;
-            buf
-        };
-        let face_recs = detection::detect_faces(&image_buf)?;
-        Ok(Solution {
-            face_tiles: face_recs.iter().map(face_rect_to_usize).collect()
-        })
-    }
-
-    fn challenge_name() -> &'static str { "basic_face_detection" }
-}
\ No newline at end of file
+#[hackattic_challenge(name = "basic_face_detection")]
+fn solve(problem: &Problem) -> Result<Solution, Error> {
+    let image_buf = fetch_asset(&problem.image_url)?;
+    let face_recs = detection::detect_faces(&image_buf)?;
+    Ok(Solution {
+        face_tiles: face_recs.iter().map(face_rect_to_usize).collect()
+    })
+}
@@ -0,0 +1,100 @@
+//! Recording of fetched problems, computed solutions and server responses,
+//! so a solver can be re-ran against the exact problem that previously failed.
+
+use failure::{Error, ResultExt};
+use serde;
+use serde_json::{self, Value};
+use std::fmt::Debug;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use super::{Config, HackatticChallenge, check_verification};
+use super::error::deserialize_problem;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Fixture {
+    pub challenge: String,
+    /// The problem JSON exactly as returned by the server
+    pub problem: Value,
+    #[serde(default)]
+    pub solution: Option<Value>,
+    #[serde(default)]
+    pub response: Option<String>
+}
+
+impl Fixture {
+    pub fn new(challenge: &str, problem: Value) -> Self {
+        Fixture {
+            challenge: challenge.to_owned(),
+            problem,
+            solution: None,
+            response: None
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Fixture, Error> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|_| format!("couldn't open fixture {}", path.display()))?;
+        let fixture = serde_json::from_reader(file)
+            .with_context(|_| format!("couldn't parse fixture {}", path.display()))?;
+        Ok(fixture)
+    }
+
+    pub fn check_challenge(&self, challenge_name: &str) -> Result<(), Error> {
+        if self.challenge != challenge_name {
+            bail!("fixture belongs to \"{}\", not \"{}\"", self.challenge, challenge_name);
+        }
+        Ok(())
+    }
+
+    /// Fails if a solution was recorded and `solution` differs from it
+    pub fn check_solution<S: serde::Serialize>(&self, solution: &S) -> Result<(), Error> {
+        if let Some(ref recorded) = self.solution {
+            let computed = serde_json::to_value(solution)?;
+            if computed != *recorded {
+                bail!("solution differs from the recorded one\nrecorded: {}\ncomputed: {}", recorded, computed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves the fixture as `<dir>/<challenge>/<unix millis>.json`, returning its path.
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> Result<PathBuf, Error> {
+        let dir = dir.as_ref().join(&self.challenge);
+        fs::create_dir_all(&dir)?;
+        let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        let millis = since_epoch.as_secs() * 1000 + (since_epoch.subsec_nanos() / 1000000) as u64;
+        let path = dir.join(format!("{}.json", millis));
+        serde_json::to_writer_pretty(File::create(&path)?, self)?;
+        Ok(path)
+    }
+}
+
+/// Solves the problem stored in a fixture without contacting the server. If the fixture
+/// also recorded a solution, fails when the newly computed solution differs from it.
+pub fn replay<C: HackatticChallenge>(path: &Path) -> Result<C::Solution, Error>
+    where C::Problem: serde::de::DeserializeOwned + Debug, C::Solution: serde::Serialize + Debug
+{
+    let fixture = Fixture::load(path)?;
+    fixture.check_challenge(C::challenge_name())?;
+    let problem: C::Problem = deserialize_problem(fixture.problem.clone())?;
+    let solution = C::make_solution(&problem)?;
+    check_verification(C::challenge_name(), C::verify(&problem, &solution), Config::current()?.force)?;
+    fixture.check_solution(&solution)?;
+    Ok(solution)
+}
+
+#[test]
+fn can_save_and_load_fixture() {
+    let dir = ::std::env::temp_dir().join("hackattic_fixture_test");
+    let mut fixture = Fixture::new("mini_miner", json!({ "difficulty": 8 }));
+    fixture.solution = Some(json!({ "nonce": 45 }));
+    let path = fixture.save(&dir).unwrap();
+    assert!(path.starts_with(dir.join("mini_miner")));
+    let loaded = Fixture::load(&path).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(loaded.problem, fixture.problem);
+    assert_eq!(loaded.solution, fixture.solution);
+    assert_eq!(loaded.response, None);
+}
@@ -0,0 +1,241 @@
+//! Formatting of floats in solutions, and a tolerant comparison for checking them.
+//!
+//! Floats are widened to an `f64` by default, which is what Hackattic seems to expect, but
+//! that serializes e.g 0.1 as 0.10000000149011612. Solutions report floats through
+//! `ReportedFloat` so that the format can be changed with the `float_format` setting, e.g
+//! to the shortest decimal which round-trips back to the same `f32`.
+
+use failure::Error;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FloatFormat {
+    /// The shortest decimal that parses back to the same `f32`, e.g 0.1
+    Shortest,
+    /// The `f32` widened to an `f64`, e.g 0.10000000149011612
+    Widened,
+    /// A C99 hex float string, e.g "0x1.99999ap-4"
+    HexFloat,
+    /// The IEEE 754 bits as a hex string, e.g "0x3dcccccd"
+    Bits
+}
+
+impl Default for FloatFormat {
+    fn default() -> Self {
+        FloatFormat::Widened
+    }
+}
+
+impl FloatFormat {
+    /// Whether floats are reported as JSON strings rather than numbers, which is only
+    /// useful for debugging as Hackattic won't accept them
+    pub fn is_string(&self) -> bool {
+        match *self {
+            FloatFormat::HexFloat | FloatFormat::Bits => true,
+            FloatFormat::Shortest | FloatFormat::Widened => false
+        }
+    }
+}
+
+impl FromStr for FloatFormat {
+    type Err = Error;
+    fn from_str(format: &str) -> Result<FloatFormat, Error> {
+        match format {
+            "shortest" => Ok(FloatFormat::Shortest),
+            "widened" => Ok(FloatFormat::Widened),
+            "hex_float" | "hex" => Ok(FloatFormat::HexFloat),
+            "bits" => Ok(FloatFormat::Bits),
+            _ => bail!("unknown float format \"{}\", expected shortest, widened, hex_float or bits", format)
+        }
+    }
+}
+
+/// An `f32` serialized in the given format. Non-finite values are `null` in the
+/// decimal formats, as JSON has no numbers for them.
+#[derive(Debug, Clone, Copy)]
+pub struct ReportedFloat {
+    pub value: f32,
+    pub format: FloatFormat
+}
+
+impl ReportedFloat {
+    pub fn new(value: f32, format: FloatFormat) -> ReportedFloat {
+        ReportedFloat { value, format }
+    }
+}
+
+impl Serialize for ReportedFloat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.format {
+            FloatFormat::Shortest => serializer.serialize_f64(shortest_f64(self.value)),
+            FloatFormat::Widened => serializer.serialize_f64(self.value as f64),
+            FloatFormat::HexFloat => serializer.serialize_str(&hex_float(self.value)),
+            FloatFormat::Bits => serializer.serialize_str(&format!("0x{:08x}", self.value.to_bits()))
+        }
+    }
+}
+
+/// The `f64` closest to the shortest decimal form of `value`, which serializes as that decimal
+pub fn shortest_f64(value: f32) -> f64 {
+    if !value.is_finite() {
+        return value as f64;
+    }
+    // `Display` of a float already prints the shortest digits that round-trip
+    value.to_string().parse().unwrap()
+}
+
+/// Formats like C's `printf("%a")`, e.g "0x1.8p+1" for 3.0 and "0x1p-149" for the smallest subnormal
+pub fn hex_float(value: f32) -> String {
+    if value.is_nan() {
+        return "nan".to_owned();
+    }
+    let sign = if value.is_sign_negative() { "-" } else { "" };
+    if value.is_infinite() {
+        return format!("{}inf", sign);
+    }
+    let bits = value.to_bits();
+    let mut exponent = ((bits >> 23) & 0xff) as i32 - 127;
+    let mut fraction = bits & 0x7f_ffff;
+    if exponent == -127 {
+        if fraction == 0 {
+            return format!("{}0x0p+0", sign);
+        }
+        // a subnormal, normalized as C does once it's widened to a double
+        let shift = fraction.leading_zeros() - 8;
+        fraction = (fraction << shift) & 0x7f_ffff;
+        exponent = -126 - shift as i32;
+    }
+    // 23 bits of fraction, shifted to fill 6 hex digits
+    let fraction = format!("{:06x}", fraction << 1);
+    let fraction = fraction.trim_right_matches('0');
+    if fraction.is_empty() {
+        format!("{}0x1p{:+}", sign, exponent)
+    } else {
+        format!("{}0x1.{}p{:+}", sign, fraction, exponent)
+    }
+}
+
+/// Parses what `hex_float` formats, or any other C99 hex float that fits an `f32`
+pub fn parse_hex_float(hex: &str) -> Option<f32> {
+    let (negative, hex) = match hex.trim() {
+        hex if hex.starts_with('-') => (true, &hex[1..]),
+        hex if hex.starts_with('+') => (false, &hex[1..]),
+        hex => (false, hex)
+    };
+    let value = match hex {
+        "nan" => Some(::std::f32::NAN),
+        "inf" | "infinity" => Some(::std::f32::INFINITY),
+        _ if hex.starts_with("0x") || hex.starts_with("0X") => {
+            let hex = &hex[2..];
+            let (digits, exponent) = match hex.find(|c: char| c == 'p' || c == 'P') {
+                Some(p) => (&hex[..p], hex[p + 1..].parse::<i32>().ok()?),
+                None => (hex, 0)
+            };
+            let (integer, fraction) = match digits.find('.') {
+                Some(dot) => (&digits[..dot], &digits[dot + 1..]),
+                None => (digits, "")
+            };
+            // more digits than a u64 holds can't be an f32 anyway
+            if integer.len() + fraction.len() > 15 || integer.len() + fraction.len() == 0 {
+                return None;
+            }
+            let mantissa = u64::from_str_radix(&format!("{}{}", integer, fraction), 16).ok()?;
+            Some((mantissa as f64 * 2f64.powi(exponent - 4 * fraction.len() as i32)) as f32)
+        },
+        _ => None
+    };
+    value.map(|value| if negative { -value } else { value })
+}
+
+/// Reads a float reported in any `FloatFormat` back, e.g to check a solution. `null` reads as NaN.
+pub fn parse_reported(value: &Value) -> Option<f32> {
+    match *value {
+        Value::Number(ref number) => number.as_f64().map(|number| number as f32),
+        Value::Null => Some(::std::f32::NAN),
+        Value::String(ref string) if string.len() == 10 && string.starts_with("0x") && !string.contains('.') && !string.contains('p') =>
+            u32::from_str_radix(&string[2..], 16).ok().map(f32::from_bits),
+        Value::String(ref string) => parse_hex_float(string),
+        _ => None
+    }
+}
+
+// maps the bits so that adjacent floats are adjacent integers, and -0.0 and 0.0 are both 0
+fn ordered_f32(value: f32) -> i64 {
+    let bits = value.to_bits();
+    if bits & 0x8000_0000 != 0 { -((bits & 0x7fff_ffff) as i64) } else { bits as i64 }
+}
+
+fn ordered_f64(value: f64) -> i128 {
+    let bits = value.to_bits();
+    if bits & 0x8000_0000_0000_0000 != 0 { -((bits & 0x7fff_ffff_ffff_ffff) as i128) } else { bits as i128 }
+}
+
+/// Whether there are at most `max_ulps` representable `f32`s between `a` and `b`. Unlike `==`,
+/// NaN equals NaN, since a self-check wants to know whether the same value was reported.
+pub fn ulps_eq_f32(a: f32, b: f32, max_ulps: u32) -> bool {
+    if a.is_nan() || b.is_nan() {
+        return a.is_nan() && b.is_nan();
+    }
+    (ordered_f32(a) - ordered_f32(b)).abs() <= max_ulps as i64
+}
+
+/// See `ulps_eq_f32`
+pub fn ulps_eq_f64(a: f64, b: f64, max_ulps: u64) -> bool {
+    if a.is_nan() || b.is_nan() {
+        return a.is_nan() && b.is_nan();
+    }
+    (ordered_f64(a) - ordered_f64(b)).abs() <= max_ulps as i128
+}
+
+#[test]
+fn reports_shortest_decimal() {
+    let json = |value: f32, format| ::serde_json::to_string(&ReportedFloat::new(value, format)).unwrap();
+    assert_eq!(json(0.1, FloatFormat::Shortest), "0.1");
+    assert_eq!(json(0.1, FloatFormat::Widened), "0.10000000149011612");
+    assert_eq!(json(-123.456, FloatFormat::Shortest), "-123.456");
+    assert_eq!(json(::std::f32::NAN, FloatFormat::Shortest), "null");
+    assert_eq!(json(0.1, FloatFormat::Bits), "\"0x3dcccccd\"");
+    assert_eq!(json(0.1, FloatFormat::HexFloat), "\"0x1.99999ap-4\"");
+}
+
+#[test]
+fn formats_hex_floats() {
+    assert_eq!(hex_float(3.0), "0x1.8p+1");
+    assert_eq!(hex_float(1.0), "0x1p+0");
+    assert_eq!(hex_float(-0.0), "-0x0p+0");
+    assert_eq!(hex_float(f32::from_bits(1)), "0x1p-149");
+    assert_eq!(hex_float(f32::from_bits(0x0060_0000)), "0x1.8p-127");
+    assert_eq!(hex_float(::std::f32::NEG_INFINITY), "-inf");
+    for &value in [0.1f32, -3.0, ::std::f32::MAX, ::std::f32::MIN_POSITIVE, f32::from_bits(1), f32::from_bits(0x7f_ffff)].iter() {
+        assert_eq!(parse_hex_float(&hex_float(value)).map(f32::to_bits), Some(value.to_bits()), "{}", hex_float(value));
+    }
+    assert_eq!(parse_hex_float("0x10"), Some(16.0));
+    assert_eq!(parse_hex_float("1.5"), None);
+}
+
+#[test]
+fn reads_every_format_back() {
+    let formats = [FloatFormat::Shortest, FloatFormat::Widened, FloatFormat::HexFloat, FloatFormat::Bits];
+    for &value in [0.1f32, -123.456, 1e-40, ::std::f32::MAX].iter() {
+        for &format in formats.iter() {
+            let json = ::serde_json::to_value(&ReportedFloat::new(value, format)).unwrap();
+            assert_eq!(parse_reported(&json).map(f32::to_bits), Some(value.to_bits()), "{} as {:?}", value, format);
+        }
+    }
+}
+
+#[test]
+fn compares_within_ulps() {
+    let next = |value: f32| f32::from_bits(value.to_bits() + 1);
+    assert!(ulps_eq_f32(1.0, next(1.0), 1));
+    assert!(!ulps_eq_f32(1.0, next(next(1.0)), 1));
+    assert!(ulps_eq_f32(0.0, -0.0, 0));
+    assert!(ulps_eq_f32(f32::from_bits(1), -f32::from_bits(1), 2));
+    assert!(ulps_eq_f32(::std::f32::NAN, ::std::f32::NAN, 0));
+    assert!(!ulps_eq_f32(::std::f32::NAN, 1.0, 1000));
+    assert!(ulps_eq_f64(0.1 + 0.2, 0.3, 1));
+    assert!(!ulps_eq_f64(0.1 + 0.2, 0.3, 0));
+}
@@ -1,21 +1,11 @@
-extern crate hackattic;
-extern crate rdb;
-#[macro_use]
-extern crate serde_derive;
-extern crate serde_json;
-extern crate serde;
-#[macro_use]
-extern crate failure;
-extern crate reqwest;
-
-use hackattic::{HackatticChallenge, make_reqwest_client};
+use super::Config;
+use reqwest;
 use failure::{Error, ResultExt};
 use std::thread;
 use std::sync::mpsc;
 use std::time::{Instant, Duration};
 
 static PROXIES: &'static [&str] = &[
-    "http://127.0.0.1:8888", // fiddler
     // random https proxies from the web
     "http://51.15.83.8:3128",
     "http://190.0.35.6:3128",
@@ -46,12 +36,13 @@ struct Answer {
 
 
 
-fn main() {
-    GlobalPresence::process_challenge().unwrap();
-}
-
 fn call_with_proxy(presence_token: &str, proxy: &str ) -> Result<String, Error> {
-    let client = reqwest::Client::builder()
+    // each request must leave through its own proxy, so only the configured certificates apply
+    let mut builder = reqwest::Client::builder();
+    for certificate in Config::current()?.proxy.certificates()? {
+        builder.add_root_certificate(certificate);
+    }
+    let client = builder
         .proxy(reqwest::Proxy::https(proxy)?)
         .build()?;
     let mut resp = client.get(&format!("https://hackattic.com/_/presence/{}", presence_token))
@@ -62,7 +53,10 @@ fn call_with_proxy(presence_token: &str, proxy: &str ) -> Result<String, Error>
 
 fn coordinate_https(token: &str) -> Result<(), Error> {
     let (tx, rx) = mpsc::channel();
-    for &proxy in PROXIES.iter() {
+    // the configured HTTPS proxy(if any) is used as one more location
+    let mut proxies = PROXIES.iter().map(|&proxy| proxy.to_owned()).collect::<Vec<_>>();
+    proxies.extend(Config::current()?.proxy.https.clone());
+    for proxy in proxies.iter().cloned() {
         let tx = tx.clone();
         let token = token.to_owned();
         thread::spawn(move || {
@@ -70,37 +64,29 @@ fn coordinate_https(token: &str) -> Result<(), Error> {
             tx.send(res).unwrap();
         });
     }
-    println!("Spawned {} threads", PROXIES.len());
+    info!("Spawned {} threads", proxies.len());
     let mut count = 0;
-    let mut nations = std::collections::HashSet::new();
+    let mut nations = ::std::collections::HashSet::new();
     let begin = Instant::now();
-    while count < PROXIES.len() && nations.len() < 7 &&
+    while count < proxies.len() && nations.len() < 7 &&
           Instant::now().duration_since(begin) < Duration::from_secs(25) {
         rx.recv().map(|r| match r {
            Ok(s) => {
                nations.extend(s.split(',').map(|s| s.to_owned()));
            },
-            Err(e) => eprintln!("HTTP request yielded an error: {:?}", e)
+            Err(e) => warn!("HTTP request yielded an error: {:?}", e)
         }).unwrap_or_else(|e| {
-            eprintln!("Error receiving: {:?}", e)
+            warn!("Error receiving: {:?}", e)
         });
         count += 1;
     }
-    println!("Finished with {} unique nations: {:?}", nations.len(), nations);
+    info!("Finished with {} unique nations: {:?}", nations.len(), nations);
     Ok(())
 }
 
 
-struct GlobalPresence;
-impl HackatticChallenge for GlobalPresence {
-    type Problem = Problem;
-    type Solution = Answer;
-    fn challenge_name() -> &'static str {
-        "a_global_presence"
-    }
-    fn make_solution(req: &Problem) -> Result<Answer, Error> {
-        coordinate_https(&req.presence_token)?;
-        Ok(Answer {})
-    }
-
+#[hackattic_challenge(name = "a_global_presence")]
+fn solve(req: &Problem) -> Result<Answer, Error> {
+    coordinate_https(&req.presence_token)?;
+    Ok(Answer {})
 }
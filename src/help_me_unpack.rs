@@ -0,0 +1,124 @@
+use super::{Base64, Config};
+use super::floats::{FloatFormat, ReportedFloat, parse_reported, ulps_eq_f32};
+use super::inspect::hexdump;
+use super::layout::{Layout, Value};
+use failure::Error;
+use serde_json;
+
+/// int, uint, short, 2 padding bytes, float, double, and a big endian double
+const LAYOUT: &'static str = "<iIh2xfd>d";
+
+#[derive(Debug, Deserialize)]
+struct Problem {
+    bytes: Base64
+}
+
+#[derive(Debug, Serialize)]
+pub struct Solution {
+    pub int: i32,
+    pub uint: u32,
+    pub short: i16,
+    // Hackattic seems to expect this to have a double precision, even though it's read as a float.
+    // The format is configurable(see `floats`), but it's widened to an f64 by default.
+    pub float: ReportedFloat,
+    pub double: f64,
+    pub big_endian_double: f64
+}
+
+#[hackattic_challenge(name = "help_me_unpack", verify = "verify_float")]
+fn solve(problem: &Problem) -> Result<Solution, Error> {
+    let float_format = Config::current()?.float_format;
+    if float_format.is_string() {
+        warn!("float_format is {:?}, which reports the float as a string. Hackattic expects a number, \
+               so only use it for debugging.", float_format);
+    }
+    solve_with(problem, float_format)
+}
+
+fn solve_with(problem: &Problem, float_format: FloatFormat) -> Result<Solution, Error> {
+    debug!("Input has {} bytes.", problem.bytes.len());
+    debug!("Input is\n{}", hexdump(&problem.bytes));
+    let values = Layout::parse(LAYOUT)?.decode(&problem.bytes)?;
+    Ok(Solution {
+        int: values.get(0)?,
+        uint: values.get(1)?,
+        short: values.get(2)?,
+        float: ReportedFloat::new(values.get(3)?, float_format),
+        double: values.get(4)?,
+        big_endian_double: values.get(5)?
+    })
+}
+
+/// Checks that the float reads back as the same `f32` in the format it's reported in,
+/// e.g an infinity can't be reported as a JSON number.
+fn verify_float(problem: &Problem, solution: &Solution) -> Result<(), Error> {
+    let float: f32 = Layout::parse(LAYOUT)?.decode(&problem.bytes)?.get(3)?;
+    let reported = serde_json::to_value(&solution.float)?;
+    match parse_reported(&reported) {
+        Some(read_back) if ulps_eq_f32(read_back, float, 0) => Ok(()),
+        _ => bail!("float {} is reported as {}, which doesn't read back as the same value", float, reported)
+    }
+}
+
+/// Packs a solution back into the problem's bytes, e.g to generate problems to test the solver with
+pub fn pack(solution: &Solution) -> Result<Vec<u8>, Error> {
+    let values = vec![
+        Value::from(solution.int),
+        Value::from(solution.uint),
+        Value::from(solution.short),
+        Value::from(solution.float.value),
+        Value::from(solution.double),
+        Value::from(solution.big_endian_double)
+    ];
+    Ok(Layout::parse(LAYOUT)?.encode(&values)?)
+}
+
+#[test]
+fn solves_recorded_fixture() {
+    // solved with an explicit format rather than through `fixtures::replay`, which would
+    // use whatever `float_format` the global config has
+    let fixture = ::fixtures::Fixture::load("fixtures/help_me_unpack/regression.json").unwrap();
+    let problem: Problem = ::error::deserialize_problem(fixture.problem.clone()).unwrap();
+    let solution = solve_with(&problem, FloatFormat::Widened).unwrap();
+    verify_float(&problem, &solution).unwrap();
+    fixture.check_solution(&solution).unwrap();
+}
+
+#[test]
+fn round_trips_arbitrary_values() {
+    // xorshift, so that failures are reproducible
+    let mut state = 0x2545f4914f6cdd1du64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let same_f64 = |a: f64, b: f64| a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan());
+    let special_floats = [0.0, -0.0, ::std::f32::INFINITY, ::std::f32::NEG_INFINITY, ::std::f32::NAN,
+                          f32::from_bits(1), ::std::f32::MIN_POSITIVE / 2.0, ::std::f32::MAX];
+    let special_doubles = [0.0, -0.0, ::std::f64::INFINITY, ::std::f64::NEG_INFINITY, ::std::f64::NAN,
+                           f64::from_bits(1), ::std::f64::MIN_POSITIVE / 2.0, ::std::f64::MIN];
+    for round in 0..1000 {
+        // random bit patterns cover NaNs and subnormals too, but rarely, so the special values are mixed in
+        let (float, double) = match round {
+            round if round < special_floats.len() => (special_floats[round], special_doubles[round]),
+            _ => (f32::from_bits(next() as u32), f64::from_bits(next()))
+        };
+        let expected = Solution {
+            int: next() as i32,
+            uint: next() as u32,
+            short: next() as i16,
+            float: ReportedFloat::new(float, FloatFormat::Widened),
+            double,
+            big_endian_double: if round % 2 == 0 { double } else { f64::from_bits(next()) }
+        };
+        let problem = Problem { bytes: pack(&expected).unwrap().into() };
+        let solution = solve_with(&problem, FloatFormat::Widened).unwrap();
+        assert_eq!((solution.int, solution.uint, solution.short), (expected.int, expected.uint, expected.short));
+        assert!(ulps_eq_f32(solution.float.value, float, 0), "float {} != {}", solution.float.value, float);
+        assert!(same_f64(solution.double, expected.double), "double {} != {}", solution.double, expected.double);
+        assert!(same_f64(solution.big_endian_double, expected.big_endian_double),
+                "big endian double {} != {}", solution.big_endian_double, expected.big_endian_double);
+    }
+}
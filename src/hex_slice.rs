@@ -4,20 +4,41 @@ extern crate hex;
 pub struct HexSlice<'a>(pub &'a [u8]);
 
 impl<'a> HexSlice<'a> {
-    pub fn new<T>(data: &'a T) -> HexSlice<'a> 
+    pub fn new<T>(data: &'a T) -> HexSlice<'a>
         where T: ?Sized + AsRef<[u8]> + 'a
     {
         HexSlice(data.as_ref())
     }
 }
 
-// You can even choose to implement multiple traits, like Lower and UpperHex
+// every byte is zero-padded, otherwise e.g [0x0a, 0x01] and [0xa0, 0x01] would both print as "a1"
 impl<'a> fmt::Display for HexSlice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl<'a> fmt::LowerHex for HexSlice<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for byte in self.0 {
-            // Decide if you want to pad out the value here
-            write!(f, "{:x}", byte)?;
+            write!(f, "{:02x}", byte)?;
         }
         Ok(())
     }
 }
+
+impl<'a> fmt::UpperHex for HexSlice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn pads_bytes() {
+    let data = [0x0a, 0x01, 0xff];
+    assert_eq!(HexSlice::new(&data).to_string(), "0a01ff");
+    assert_eq!(format!("{:X}", HexSlice::new(&data)), "0A01FF");
+}
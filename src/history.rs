@@ -0,0 +1,186 @@
+//! A local history of challenge runs, kept as an append-only JSONL file so that
+//! regressions of a solver can be spotted over time. See the `history` binary.
+
+use failure::{Error, ResultExt};
+use hex;
+use ring::digest;
+use serde_json::{self, Value};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use super::{Config, Outcome, SubmissionResult, Timings};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Run {
+    pub challenge: String,
+    /// Unix time, in seconds, of when the run finished
+    pub timestamp: u64,
+    /// SHA-256 of the problem JSON, `None` if fetching it failed
+    pub problem_hash: Option<String>,
+    pub solution: Option<Value>,
+    /// `None` if the run failed before a response was received, see `error`
+    pub outcome: Option<Outcome>,
+    pub error: Option<String>,
+    pub playground: bool,
+    pub fetch_ms: Option<u64>,
+    pub solve_ms: Option<u64>,
+    pub submit_ms: Option<u64>
+}
+
+fn as_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + (duration.subsec_nanos() / 1000000) as u64
+}
+
+impl Run {
+    pub fn new(challenge: &str, problem: Option<&Value>, solution: Option<&Value>, timings: &Timings,
+               result: Result<&SubmissionResult, &Error>, playground: bool) -> Run {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|since_epoch| since_epoch.as_secs()).unwrap_or(0);
+        Run {
+            challenge: challenge.to_owned(),
+            timestamp,
+            problem_hash: problem.map(hash_problem),
+            solution: solution.cloned(),
+            outcome: result.ok().map(|result| result.outcome.clone()),
+            error: result.err().map(|e| e.to_string()),
+            playground,
+            fetch_ms: timings.fetch.map(as_millis),
+            solve_ms: timings.solve.map(as_millis),
+            submit_ms: timings.submit.map(as_millis)
+        }
+    }
+
+    pub fn is_accepted(&self) -> bool {
+        self.outcome == Some(Outcome::Accepted)
+    }
+}
+
+pub fn hash_problem(problem: &Value) -> String {
+    let hash = digest::digest(&digest::SHA256, problem.to_string().as_bytes());
+    hex::encode(hash.as_ref())
+}
+
+pub fn append<P: AsRef<Path>>(path: P, run: &Run) -> Result<(), Error> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_vec(run)?;
+    line.push(b'\n');
+    OpenOptions::new().create(true).append(true).open(path)
+        .and_then(|mut file| file.write_all(&line))
+        .with_context(|_| format!("couldn't append to history file {}", path.display()))?;
+    Ok(())
+}
+
+/// Appends `run` to the configured history file, if any. Failing to do so
+/// only warns, as it shouldn't fail the run itself.
+pub fn record(config: &Config, run: &Run) {
+    if let Some(ref path) = config.history_file {
+        if let Err(e) = append(path, run) {
+            warn!("{}", e);
+        }
+    }
+}
+
+/// Loads every run, skipping lines that can't be parsed(e.g a partially written last line).
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<Run>, Error> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .with_context(|_| format!("couldn't open history file {}", path.display()))?;
+    let mut runs = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(run) => runs.push(run),
+            Err(e) => warn!("skipping line {} of {}: {}", index + 1, path.display(), e)
+        }
+    }
+    Ok(runs)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub challenge: String,
+    pub runs: usize,
+    pub accepted: usize,
+    /// Median over the runs that finished solving
+    pub median_solve: Option<Duration>,
+    /// Unix time of the latest run
+    pub last_run: u64
+}
+
+impl Stats {
+    pub fn success_rate(&self) -> f64 {
+        self.accepted as f64 / self.runs as f64
+    }
+}
+
+/// Per-challenge statistics, sorted by challenge name
+pub fn stats(runs: &[Run]) -> Vec<Stats> {
+    let mut by_challenge = BTreeMap::new();
+    for run in runs {
+        by_challenge.entry(&run.challenge).or_insert_with(Vec::new).push(run);
+    }
+    by_challenge.into_iter().map(|(challenge, runs)| {
+        let mut solve_times = runs.iter().filter_map(|run| run.solve_ms).collect::<Vec<_>>();
+        solve_times.sort();
+        let median_solve = match solve_times.len() {
+            0 => None,
+            len if len % 2 == 1 => Some(solve_times[len / 2]),
+            len => Some((solve_times[len / 2 - 1] + solve_times[len / 2]) / 2)
+        };
+        Stats {
+            challenge: challenge.clone(),
+            runs: runs.len(),
+            accepted: runs.iter().filter(|run| run.is_accepted()).count(),
+            median_solve: median_solve.map(Duration::from_millis),
+            last_run: runs.iter().map(|run| run.timestamp).max().unwrap_or(0)
+        }
+    }).collect()
+}
+
+#[test]
+fn computes_stats_per_challenge() {
+    let run = |challenge: &str, outcome, solve_ms| Run {
+        challenge: challenge.to_owned(),
+        timestamp: 0,
+        problem_hash: None,
+        solution: None,
+        outcome,
+        error: None,
+        playground: false,
+        fetch_ms: None,
+        solve_ms,
+        submit_ms: None
+    };
+    let runs = vec![
+        run("mini_miner", Some(Outcome::Accepted), Some(100)),
+        run("mini_miner", Some(Outcome::Rejected("wrong nonce".to_owned())), Some(300)),
+        run("mini_miner", None, None),
+        run("password_hashing", Some(Outcome::Accepted), Some(2000))
+    ];
+    let stats = stats(&runs);
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0].challenge, "mini_miner");
+    assert_eq!((stats[0].runs, stats[0].accepted), (3, 1));
+    assert_eq!(stats[0].median_solve, Some(Duration::from_millis(200)));
+    assert_eq!(stats[1].median_solve, Some(Duration::from_millis(2000)));
+}
+
+#[test]
+fn can_append_and_load_runs() {
+    let path = ::std::env::temp_dir().join("hackattic_history_test.jsonl");
+    let _ = fs::remove_file(&path);
+    let result = SubmissionResult::from_response(200, r#"{"result": "passed"}"#.to_owned(), false);
+    let run = Run::new("mini_miner", Some(&json!({ "difficulty": 8 })), None, &Timings::default(), Ok(&result), false);
+    append(&path, &run).unwrap();
+    append(&path, &run).unwrap();
+    let runs = load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(runs, vec![run.clone(), run]);
+}
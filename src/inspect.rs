@@ -0,0 +1,179 @@
+//! Formatting for inspecting binary payloads(e.g RDB files, dumps, DER certificates)
+//! while debugging a solver, e.g `debug!("{}", hexdump(&bytes))`.
+
+use std::fmt;
+use super::HexSlice;
+
+const BYTES_PER_LINE: usize = 16;
+
+fn printable(byte: u8) -> char {
+    if byte.is_ascii_graphic() || byte == b' ' {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+/// An `xxd` style dump: offset, 16 bytes in groups of 2, and their ASCII
+pub struct Hexdump<'a>(pub &'a [u8]);
+
+pub fn hexdump<T: ?Sized + AsRef<[u8]>>(data: &T) -> Hexdump {
+    Hexdump(data.as_ref())
+}
+
+fn write_hex_line(f: &mut fmt::Formatter, line: &[u8]) -> fmt::Result {
+    for index in 0..BYTES_PER_LINE {
+        match line.get(index) {
+            Some(byte) => write!(f, "{:02x}", byte)?,
+            None => f.write_str("  ")?
+        }
+        if index % 2 == 1 && index != BYTES_PER_LINE - 1 {
+            f.write_str(" ")?;
+        }
+    }
+    Ok(())
+}
+
+impl<'a> fmt::Display for Hexdump<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, line) in self.0.chunks(BYTES_PER_LINE).enumerate() {
+            if index > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{:08x}: ", index * BYTES_PER_LINE)?;
+            write_hex_line(f, line)?;
+            f.write_str("  ")?;
+            for &byte in line {
+                write!(f, "{}", printable(byte))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The offset of the first byte that differs, or of the end of the shorter buffer
+pub fn first_difference(left: &[u8], right: &[u8]) -> Option<usize> {
+    if left == right {
+        return None;
+    }
+    Some(left.iter().zip(right).position(|(l, r)| l != r).unwrap_or(left.len().min(right.len())))
+}
+
+/// Two buffers side by side, one line per 16 bytes. Lines which are equal in both are
+/// collapsed into "...", and differing bytes are marked with `^` below the line.
+pub struct Diff<'a> {
+    pub left: &'a [u8],
+    pub right: &'a [u8]
+}
+
+pub fn diff<'a, L, R>(left: &'a L, right: &'a R) -> Diff<'a>
+    where L: ?Sized + AsRef<[u8]>, R: ?Sized + AsRef<[u8]>
+{
+    Diff {
+        left: left.as_ref(),
+        right: right.as_ref()
+    }
+}
+
+impl<'a> fmt::Display for Diff<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.left == self.right {
+            return write!(f, "buffers are identical({} bytes)", self.left.len());
+        }
+        let len = self.left.len().max(self.right.len());
+        let line_of = |data: &'a [u8], offset: usize| &data[offset.min(data.len())..(offset + BYTES_PER_LINE).min(data.len())];
+        let mut skipped = false;
+        for offset in (0..len).step_by(BYTES_PER_LINE) {
+            let (left, right) = (line_of(self.left, offset), line_of(self.right, offset));
+            if left == right {
+                if !skipped {
+                    f.write_str("...\n")?;
+                    skipped = true;
+                }
+                continue;
+            }
+            skipped = false;
+            write!(f, "{:08x}: ", offset)?;
+            write_hex_line(f, left)?;
+            f.write_str(" | ")?;
+            write_hex_line(f, right)?;
+            f.write_str("\n          ")?;
+            let mut markers = String::new();
+            for index in 0..BYTES_PER_LINE {
+                markers.push_str(if left.get(index) != right.get(index) { "^^" } else { "  " });
+                if index % 2 == 1 && index != BYTES_PER_LINE - 1 {
+                    markers.push(' ');
+                }
+            }
+            writeln!(f, "{}", markers.trim_right())?;
+        }
+        write!(f, "left is {} bytes, right is {} bytes", self.left.len(), self.right.len())
+    }
+}
+
+/// `Debug`s a blob as its length and the hex of its first and last bytes, so that
+/// logging a multi-megabyte payload stays readable.
+pub struct Truncated<'a> {
+    data: &'a [u8],
+    max_bytes: usize
+}
+
+impl<'a> Truncated<'a> {
+    /// Shows at most `max_bytes` bytes, half from the start and half from the end
+    pub fn new<T: ?Sized + AsRef<[u8]>>(data: &'a T, max_bytes: usize) -> Truncated<'a> {
+        Truncated {
+            data: data.as_ref(),
+            max_bytes
+        }
+    }
+}
+
+pub fn truncated<T: ?Sized + AsRef<[u8]>>(data: &T) -> Truncated {
+    Truncated::new(data, 32)
+}
+
+impl<'a> fmt::Debug for Truncated<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let len = self.data.len();
+        if len <= self.max_bytes {
+            return write!(f, "<{} bytes: {}>", len, HexSlice(self.data));
+        }
+        let head = (self.max_bytes + 1) / 2;
+        let tail = self.max_bytes / 2;
+        write!(f, "<{} bytes: {}..({} bytes)..{}>", len, HexSlice(&self.data[..head]), len - head - tail,
+               HexSlice(&self.data[len - tail..]))
+    }
+}
+
+#[test]
+fn dumps_like_xxd() {
+    let dump = hexdump(&b"REDIS0008\xfa\x09redis-ver\x054.0.9"[..]).to_string();
+    assert_eq!(dump, "\
+00000000: 5245 4449 5330 3030 38fa 0972 6564 6973  REDIS0008..redis
+00000010: 2d76 6572 0534 2e30 2e39                 -ver.4.0.9");
+}
+
+#[test]
+fn diffs_only_differing_lines() {
+    let left = vec![0u8; 48];
+    let mut right = left.clone();
+    right[33] = 0xff;
+    right.push(1);
+    assert_eq!(first_difference(&left, &right), Some(33));
+    assert_eq!(first_difference(&left, &left[..40]), Some(40));
+    assert_eq!(first_difference(&left, &left), None);
+    let diff = diff(&left, &right).to_string();
+    let lines = diff.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "...");
+    assert!(lines[1].starts_with("00000020: 0000 0000"));
+    assert_eq!(lines[2], "            ^^");
+    assert!(lines[3].starts_with("00000030:"));
+    assert_eq!(lines[5], "left is 48 bytes, right is 49 bytes");
+}
+
+#[test]
+fn truncates_large_blobs() {
+    let data = (0..100u8).collect::<Vec<_>>();
+    assert_eq!(format!("{:?}", Truncated::new(&data, 4)), "<100 bytes: 0001..(96 bytes)..6263>");
+    assert_eq!(format!("{:?}", truncated(&data[..2])), "<2 bytes: 0001>");
+}
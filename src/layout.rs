@@ -0,0 +1,598 @@
+//! Declarative binary layouts, described with Python `struct` style format strings,
+//! e.g `"<iIh2xfd>d"` for `help_me_unpack`.
+//!
+//! Each field is a type code, optionally prefixed with a repeat count:
+//!
+//! | code       | type          | size |
+//! |------------|---------------|------|
+//! | `x`        | padding       | 1    |
+//! | `?`        | bool          | 1    |
+//! | `b`/`B`    | i8/u8         | 1    |
+//! | `h`/`H`    | i16/u16       | 2    |
+//! | `i`/`I`    | i32/u32       | 4    |
+//! | `l`/`L`    | i32/u32       | 4, or C's `long` with `@` |
+//! | `q`/`Q`    | i64/u64       | 8    |
+//! | `f`        | f32           | 4    |
+//! | `d`        | f64           | 8    |
+//! | `s`        | bytes         | the count, e.g `16s` |
+//!
+//! Byte order is set by `<`(little), `>`/`!`(big), `=`(native) or `@`(native, with
+//! fields aligned to their size). As in Python, `@` also sizes `l`/`L` like the platform's
+//! `long`, i.e 8 bytes on 64 bit Linux and macOS. Unlike Python, it may change mid-layout, so
+//! `"<d>d"` is a little endian double followed by a big endian one.
+//!
+//! `Layout::decode` unpacks a buffer into `Value`s, and `Layout::encode` packs them back.
+
+use byteorder::{ByteOrder, BE, LE};
+use failure::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big
+}
+
+impl Endian {
+    pub fn native() -> Endian {
+        if cfg!(target_endian = "little") { Endian::Little } else { Endian::Big }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Bool,
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+    Bytes(usize)
+}
+
+impl Kind {
+    fn from_code(code: char, count: usize, native_sizes: bool) -> Option<Kind> {
+        let long_is_64 = native_sizes && ::std::mem::size_of::<::std::os::raw::c_long>() == 8;
+        Some(match code {
+            'l' if long_is_64 => Kind::I64,
+            'L' if long_is_64 => Kind::U64,
+            '?' => Kind::Bool,
+            'b' => Kind::I8,
+            'B' => Kind::U8,
+            'h' => Kind::I16,
+            'H' => Kind::U16,
+            'i' | 'l' => Kind::I32,
+            'I' | 'L' => Kind::U32,
+            'q' => Kind::I64,
+            'Q' => Kind::U64,
+            'f' => Kind::F32,
+            'd' => Kind::F64,
+            's' => Kind::Bytes(count),
+            _ => return None
+        })
+    }
+
+    pub fn code(&self) -> char {
+        match *self {
+            Kind::Bool => '?',
+            Kind::I8 => 'b',
+            Kind::U8 => 'B',
+            Kind::I16 => 'h',
+            Kind::U16 => 'H',
+            Kind::I32 => 'i',
+            Kind::U32 => 'I',
+            Kind::I64 => 'q',
+            Kind::U64 => 'Q',
+            Kind::F32 => 'f',
+            Kind::F64 => 'd',
+            Kind::Bytes(_) => 's'
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match *self {
+            Kind::Bool | Kind::I8 | Kind::U8 => 1,
+            Kind::I16 | Kind::U16 => 2,
+            Kind::I32 | Kind::U32 | Kind::F32 => 4,
+            Kind::I64 | Kind::U64 | Kind::F64 => 8,
+            Kind::Bytes(len) => len
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    pub kind: Kind,
+    pub endian: Endian,
+    /// Offset of the field from the start of the buffer
+    pub offset: usize
+}
+
+/// A decoded field. Signed integers are widened to `Int` and unsigned ones to `UInt`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    F32(f32),
+    F64(f64),
+    Bytes(Vec<u8>)
+}
+
+#[derive(Debug, Fail)]
+pub enum LayoutError {
+    #[fail(display = "invalid layout \"{}\" at position {}: {}", layout, position, message)]
+    Syntax { layout: String, position: usize, message: String },
+    #[fail(display = "field {}('{}') at offset {} needs {} bytes, but the buffer is only {} bytes long", index, code, offset, size, len)]
+    TooShort { index: usize, code: char, offset: usize, size: usize, len: usize },
+    #[fail(display = "field {} is {:?}, which can't be read as {}", index, value, expected)]
+    WrongType { index: usize, value: Value, expected: &'static str },
+    #[fail(display = "the layout has {} fields, but {} values were given", expected, got)]
+    WrongCount { expected: usize, got: usize },
+    #[fail(display = "field {}('{}') can't hold {:?}", index, code, value)]
+    Unencodable { index: usize, code: char, value: Value }
+}
+
+/// The largest layout `Layout::parse` accepts, in bytes. Repeat counts are capped by it too,
+/// so a typo like `"4000000000i"` fails instead of building billions of fields.
+pub const MAX_SIZE: usize = 1 << 20;
+
+/// A parsed format string, see the module docs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    fields: Vec<Field>,
+    size: usize
+}
+
+impl Layout {
+    pub fn parse(layout: &str) -> Result<Layout, LayoutError> {
+        let syntax_error = |position: usize, message: String| LayoutError::Syntax {
+            layout: layout.to_owned(),
+            position,
+            message
+        };
+        let mut fields = Vec::new();
+        let mut offset = 0;
+        let mut endian = Endian::native();
+        let mut aligned = true;
+        let mut count: Option<usize> = None;
+        for (position, code) in layout.char_indices() {
+            if let Some(digit) = code.to_digit(10) {
+                let repeat = count.unwrap_or(0).checked_mul(10).and_then(|repeat| repeat.checked_add(digit as usize));
+                count = Some(repeat.ok_or_else(|| syntax_error(position, "count is too large".to_owned()))?);
+                continue;
+            }
+            let order = match code {
+                '<' => Some((Endian::Little, false)),
+                '>' | '!' => Some((Endian::Big, false)),
+                '=' => Some((Endian::native(), false)),
+                '@' => Some((Endian::native(), true)),
+                _ => None
+            };
+            if let Some((order_endian, order_aligned)) = order {
+                if count.is_some() {
+                    return Err(syntax_error(position, format!("a count can't precede '{}'", code)));
+                }
+                endian = order_endian;
+                aligned = order_aligned;
+                continue;
+            }
+            if code.is_whitespace() {
+                if count.is_some() {
+                    return Err(syntax_error(position, "expected a type code after the count".to_owned()));
+                }
+                continue;
+            }
+            let repeat = count.take().unwrap_or(1);
+            if repeat > MAX_SIZE {
+                return Err(syntax_error(position, format!("count {} is larger than {}", repeat, MAX_SIZE)));
+            }
+            let advance = |offset: usize, size: usize| offset.checked_add(size).filter(|&end| end <= MAX_SIZE)
+                .ok_or_else(|| syntax_error(position, format!("the layout is larger than {} bytes", MAX_SIZE)));
+            if code == 'x' {
+                offset = advance(offset, repeat)?;
+                continue;
+            }
+            let kind = Kind::from_code(code, repeat, aligned)
+                .ok_or_else(|| syntax_error(position, format!("unknown type code '{}'", code)))?;
+            // the count of 's' is the length of a single field
+            let repeat = if code == 's' { 1 } else { repeat };
+            for _ in 0..repeat {
+                let alignment = match kind {
+                    Kind::Bytes(_) => 1,
+                    kind => kind.size()
+                };
+                if aligned {
+                    offset = advance(offset, alignment - 1)? / alignment * alignment;
+                }
+                fields.push(Field { kind, endian, offset });
+                offset = advance(offset, kind.size())?;
+            }
+        }
+        if count.is_some() {
+            return Err(syntax_error(layout.len(), "expected a type code after the count".to_owned()));
+        }
+        Ok(Layout {
+            fields,
+            size: offset
+        })
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// The size in bytes of a buffer with this layout
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Decodes every non-padding field. Bytes after the layout's end are ignored.
+    pub fn decode(&self, data: &[u8]) -> Result<Decoded, LayoutError> {
+        let mut values = Vec::with_capacity(self.fields.len());
+        for (index, field) in self.fields.iter().enumerate() {
+            let size = field.kind.size();
+            let bytes = data.get(field.offset..field.offset + size).ok_or_else(|| LayoutError::TooShort {
+                index,
+                code: field.kind.code(),
+                offset: field.offset,
+                size,
+                len: data.len()
+            })?;
+            values.push(decode_field(field, bytes));
+        }
+        if data.len() > self.size {
+            debug!("ignoring {} bytes after the end of the layout", data.len() - self.size);
+        }
+        Ok(Decoded(values))
+    }
+
+    /// Packs one value per non-padding field, padding bytes are zero. Integers must fit
+    /// their field, and a byte string shorter than its field is padded with zeros.
+    pub fn encode(&self, values: &[Value]) -> Result<Vec<u8>, LayoutError> {
+        if values.len() != self.fields.len() {
+            return Err(LayoutError::WrongCount { expected: self.fields.len(), got: values.len() });
+        }
+        let mut data = vec![0u8; self.size];
+        for (index, (field, value)) in self.fields.iter().zip(values).enumerate() {
+            let bytes = &mut data[field.offset..field.offset + field.kind.size()];
+            if !encode_field(field, value, bytes) {
+                return Err(LayoutError::Unencodable { index, code: field.kind.code(), value: value.clone() });
+            }
+        }
+        Ok(data)
+    }
+}
+
+impl FromStr for Layout {
+    type Err = LayoutError;
+    fn from_str(layout: &str) -> Result<Layout, LayoutError> {
+        Layout::parse(layout)
+    }
+}
+
+fn decode_field(field: &Field, bytes: &[u8]) -> Value {
+    macro_rules! read {
+        ($read:ident) => {
+            match field.endian {
+                Endian::Little => LE::$read(bytes),
+                Endian::Big => BE::$read(bytes)
+            }
+        }
+    }
+    match field.kind {
+        Kind::Bool => Value::Bool(bytes[0] != 0),
+        Kind::I8 => Value::Int(bytes[0] as i8 as i64),
+        Kind::U8 => Value::UInt(bytes[0] as u64),
+        Kind::I16 => Value::Int(read!(read_i16) as i64),
+        Kind::U16 => Value::UInt(read!(read_u16) as u64),
+        Kind::I32 => Value::Int(read!(read_i32) as i64),
+        Kind::U32 => Value::UInt(read!(read_u32) as u64),
+        Kind::I64 => Value::Int(read!(read_i64)),
+        Kind::U64 => Value::UInt(read!(read_u64)),
+        Kind::F32 => Value::F32(read!(read_f32)),
+        Kind::F64 => Value::F64(read!(read_f64)),
+        Kind::Bytes(_) => Value::Bytes(bytes.to_owned())
+    }
+}
+
+/// Writes `value` into the field's `bytes`, returning whether it fits the field
+fn encode_field(field: &Field, value: &Value, bytes: &mut [u8]) -> bool {
+    macro_rules! write_as {
+        ($write:ident, $value:expr) => {
+            match field.endian {
+                Endian::Little => LE::$write(bytes, $value),
+                Endian::Big => BE::$write(bytes, $value)
+            }
+        }
+    }
+    macro_rules! int {
+        ($int:ty) => {
+            match <$int>::from_value(value) {
+                Some(int) => int,
+                None => return false
+            }
+        }
+    }
+    match (field.kind, value) {
+        (Kind::Bool, &Value::Bool(value)) => bytes[0] = value as u8,
+        (Kind::I8, _) => bytes[0] = int!(i8) as u8,
+        (Kind::U8, _) => bytes[0] = int!(u8),
+        (Kind::I16, _) => write_as!(write_i16, int!(i16)),
+        (Kind::U16, _) => write_as!(write_u16, int!(u16)),
+        (Kind::I32, _) => write_as!(write_i32, int!(i32)),
+        (Kind::U32, _) => write_as!(write_u32, int!(u32)),
+        (Kind::I64, _) => write_as!(write_i64, int!(i64)),
+        (Kind::U64, _) => write_as!(write_u64, int!(u64)),
+        (Kind::F32, &Value::F32(float)) => write_as!(write_f32, float),
+        (Kind::F64, _) => write_as!(write_f64, match f64::from_value(value) {
+            Some(double) => double,
+            None => return false
+        }),
+        (Kind::Bytes(len), &Value::Bytes(ref value)) if value.len() <= len => bytes[..value.len()].copy_from_slice(value),
+        _ => return false
+    }
+    true
+}
+
+macro_rules! value_from {
+    ($variant:ident, $as:ty, $($from:ty),*) => {$(
+        impl From<$from> for Value {
+            fn from(value: $from) -> Value {
+                Value::$variant(value as $as)
+            }
+        }
+    )*}
+}
+
+value_from!(Int, i64, i8, i16, i32, i64);
+value_from!(UInt, u64, u8, u16, u32, u64);
+value_from!(F32, f32, f32);
+value_from!(F64, f64, f64);
+value_from!(Bool, bool, bool);
+
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Value {
+        Value::Bytes(bytes)
+    }
+}
+
+/// Types a `Value` can be read as. Integers are range checked, so e.g an `h` field
+/// can be read as an `i32` but a negative one can't be read as a `u32`.
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Option<Self>;
+    fn type_name() -> &'static str;
+}
+
+macro_rules! int_from_value {
+    ($($int:ty),*) => {$(
+        impl FromValue for $int {
+            fn from_value(value: &Value) -> Option<$int> {
+                let int = match *value {
+                    Value::Int(int) => int as i128,
+                    Value::UInt(uint) => uint as i128,
+                    _ => return None
+                };
+                if int < <$int>::min_value() as i128 || int > <$int>::max_value() as i128 {
+                    return None;
+                }
+                Some(int as $int)
+            }
+
+            fn type_name() -> &'static str {
+                stringify!($int)
+            }
+        }
+    )*}
+}
+
+int_from_value!(i8, u8, i16, u16, i32, u32, i64, u64);
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Option<bool> {
+        match *value {
+            Value::Bool(value) => Some(value),
+            _ => None
+        }
+    }
+
+    fn type_name() -> &'static str {
+        "bool"
+    }
+}
+
+impl FromValue for f32 {
+    fn from_value(value: &Value) -> Option<f32> {
+        match *value {
+            Value::F32(float) => Some(float),
+            _ => None
+        }
+    }
+
+    fn type_name() -> &'static str {
+        "f32"
+    }
+}
+
+// an `f` can be widened losslessly, e.g `help_me_unpack` wants its float as a double
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Option<f64> {
+        match *value {
+            Value::F32(float) => Some(float as f64),
+            Value::F64(double) => Some(double),
+            _ => None
+        }
+    }
+
+    fn type_name() -> &'static str {
+        "f64"
+    }
+}
+
+impl FromValue for Vec<u8> {
+    fn from_value(value: &Value) -> Option<Vec<u8>> {
+        match *value {
+            Value::Bytes(ref bytes) => Some(bytes.clone()),
+            _ => None
+        }
+    }
+
+    fn type_name() -> &'static str {
+        "bytes"
+    }
+}
+
+/// The values of a decoded buffer, in the layout's order(padding excluded)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded(pub Vec<Value>);
+
+impl Decoded {
+    pub fn get<T: FromValue>(&self, index: usize) -> Result<T, Error> {
+        let value = self.0.get(index).ok_or_else(|| format_err!("the layout has only {} fields, no field {}", self.0.len(), index))?;
+        T::from_value(value).ok_or_else(|| LayoutError::WrongType {
+            index,
+            value: value.clone(),
+            expected: T::type_name()
+        }.into())
+    }
+
+    pub fn into_values(self) -> Vec<Value> {
+        self.0
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut endian = None;
+        let mut offset = 0;
+        for field in &self.fields {
+            if endian != Some(field.endian) {
+                f.write_str(if field.endian == Endian::Little { "<" } else { ">" })?;
+                endian = Some(field.endian);
+            }
+            if field.offset > offset {
+                write!(f, "{}x", field.offset - offset)?;
+            }
+            match field.kind {
+                Kind::Bytes(len) => write!(f, "{}s", len)?,
+                kind => write!(f, "{}", kind.code())?
+            }
+            offset = field.offset + field.kind.size();
+        }
+        if self.size > offset {
+            write!(f, "{}x", self.size - offset)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn parses_help_me_unpack_layout() {
+    let layout = Layout::parse("<iIh2xfd>d").unwrap();
+    let offsets = layout.fields().iter().map(|field| (field.kind.code(), field.offset)).collect::<Vec<_>>();
+    assert_eq!(offsets, vec![('i', 0), ('I', 4), ('h', 8), ('f', 12), ('d', 16), ('d', 24)]);
+    assert_eq!(layout.fields()[5].endian, Endian::Big);
+    assert_eq!(layout.size(), 32);
+    assert_eq!(layout.to_string(), "<iIh2xfd>d");
+}
+
+#[test]
+fn aligns_native_layouts() {
+    let layout = Layout::parse("@bi3sh").unwrap();
+    let offsets = layout.fields().iter().map(|field| field.offset).collect::<Vec<_>>();
+    assert_eq!(offsets, vec![0, 4, 8, 12]);
+    assert_eq!(Layout::parse("=bi").unwrap().fields()[1].offset, 1);
+    let long_size = ::std::mem::size_of::<::std::os::raw::c_long>();
+    assert_eq!(Layout::parse("@bl").unwrap().size(), 2 * long_size);
+    assert_eq!(Layout::parse("<bl").unwrap().size(), 5);
+}
+
+#[test]
+fn rejects_invalid_layouts() {
+    match Layout::parse("<i2z") {
+        Err(LayoutError::Syntax { position, .. }) => assert_eq!(position, 3),
+        other => panic!("unexpected {:?}", other)
+    }
+    assert!(Layout::parse("<i2").is_err());
+    assert!(Layout::parse("2<i").is_err());
+}
+
+#[test]
+fn rejects_oversized_layouts() {
+    for layout in &["4000000000i", "<99999999999999999999x", "<1048576s1048576s", "@1048576xq"] {
+        match Layout::parse(layout) {
+            Err(LayoutError::Syntax { .. }) => {},
+            other => panic!("unexpected {:?} for {}", other, layout)
+        }
+    }
+    assert_eq!(Layout::parse("<1048576s").unwrap().size(), MAX_SIZE);
+}
+
+#[test]
+fn decodes_fields() {
+    let data = [0xff, 0xff, 0xff, 0xff, 0x01, 0x00, 0x00, 0x80, 0x00, 0x00, 0x3f, 0x80, 0x00, 0x00, b'h', b'i'];
+    let decoded = Layout::parse("<iI>xxf2s").unwrap().decode(&data).unwrap();
+    assert_eq!(decoded.get::<i32>(0).unwrap(), -1);
+    assert_eq!(decoded.get::<u32>(1).unwrap(), 0x80000001);
+    assert_eq!(decoded.get::<f64>(2).unwrap(), 1.0);
+    assert_eq!(decoded.get::<Vec<u8>>(3).unwrap(), b"hi");
+    assert!(decoded.get::<u32>(0).is_err(), "-1 isn't a u32");
+    assert!(decoded.get::<i16>(1).is_err(), "0x80000001 doesn't fit an i16");
+}
+
+#[test]
+fn reports_offset_of_truncated_field() {
+    let err = Layout::parse("<iId").unwrap().decode(&[0; 10]).unwrap_err();
+    match err {
+        LayoutError::TooShort { index, offset, size, len, .. } => assert_eq!((index, offset, size, len), (2, 8, 8, 10)),
+        other => panic!("unexpected {:?}", other)
+    }
+}
+
+#[test]
+fn encodes_fields() {
+    let layout = Layout::parse("<?bH2x>if3s").unwrap();
+    let values = vec![Value::from(true), Value::from(-2i8), Value::from(0x1234u16), Value::from(-1i32),
+                      Value::from(1.0f32), Value::from(b"hi".to_vec())];
+    let data = layout.encode(&values).unwrap();
+    assert_eq!(data, vec![1, 0xfe, 0x34, 0x12, 0, 0, 0xff, 0xff, 0xff, 0xff, 0x3f, 0x80, 0, 0, b'h', b'i', 0]);
+    let decoded = layout.decode(&data).unwrap().into_values();
+    assert_eq!(decoded[..5], values[..5]);
+    assert_eq!(decoded[5], Value::from(b"hi\0".to_vec()));
+}
+
+#[test]
+fn rejects_values_that_dont_fit() {
+    let layout = Layout::parse("<hf").unwrap();
+    assert!(layout.encode(&[Value::from(1i16)]).is_err());
+    match layout.encode(&[Value::from(40000u32), Value::from(1.0f32)]) {
+        Err(LayoutError::Unencodable { index, code, .. }) => assert_eq!((index, code), (0, 'h')),
+        other => panic!("unexpected {:?}", other)
+    }
+    // a double can't be narrowed to an 'f' without losing precision
+    assert!(layout.encode(&[Value::from(1i16), Value::from(1.0f64)]).is_err());
+}
+
+#[test]
+fn round_trips_special_floats() {
+    let layout = Layout::parse("<fd>fd").unwrap();
+    let min_subnormal_f32 = f32::from_bits(1);
+    let min_subnormal_f64 = f64::from_bits(1);
+    let floats = [0.0, -0.0, ::std::f32::INFINITY, ::std::f32::NEG_INFINITY, ::std::f32::NAN, min_subnormal_f32, -min_subnormal_f32];
+    let doubles = [0.0, -0.0, ::std::f64::INFINITY, ::std::f64::NEG_INFINITY, ::std::f64::NAN, min_subnormal_f64, ::std::f64::MAX];
+    for (&float, &double) in floats.iter().zip(doubles.iter()) {
+        let values = vec![Value::from(float), Value::from(double), Value::from(float), Value::from(double)];
+        let decoded = layout.decode(&layout.encode(&values).unwrap()).unwrap();
+        // compares bits, as NaN != NaN and 0.0 == -0.0
+        for index in [0, 2].iter().cloned() {
+            assert_eq!(decoded.get::<f32>(index).unwrap().to_bits(), float.to_bits());
+            assert_eq!(decoded.get::<f64>(index + 1).unwrap().to_bits(), double.to_bits());
+        }
+    }
+}
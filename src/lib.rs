@@ -1,46 +1,224 @@
+// lets code generated by `hackattic_derive` refer to `::hackattic` within this crate too
+extern crate self as hackattic;
 extern crate reqwest;
 #[macro_use]
 extern crate serde_derive;
+#[macro_use]
 extern crate serde_json;
 extern crate serde;
 #[macro_use]
 extern crate failure;
-#[cfg(facedetect)]
+#[cfg(feature = "facedetect")]
 extern crate cv;
 #[macro_use]
 extern crate lazy_static;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate hackattic_derive;
+extern crate env_logger;
+extern crate serde_path_to_error;
+#[cfg(windows)]
+extern crate ansi_term;
+extern crate base64;
+extern crate hex;
+extern crate byteorder;
+extern crate ring;
+extern crate openssl;
+extern crate postgres;
+extern crate tempfile;
+extern crate flate2;
+extern crate rdb_parser;
+extern crate futures;
+extern crate num_cpus;
+extern crate tokio_core;
+extern crate trust_dns_server;
+extern crate trust_dns_proto;
+extern crate websocket;
 
 use failure::Error;
 use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
 
 /* utility libraries useful for many challenges */
 mod hex_slice;
 pub use hex_slice::*;
+pub mod inspect;
+pub mod layout;
+pub mod floats;
 mod serde_utils;
 pub use serde_utils::*;
+pub mod error;
+pub use error::HackatticError;
+pub mod logging;
+pub mod history;
+pub mod fixtures;
+pub use fixtures::Fixture;
+pub mod submission;
+pub use submission::{SubmissionResult, Outcome};
+pub mod config;
+pub use config::Config;
+pub mod assets;
+pub use assets::fetch_asset;
+pub mod proxy;
+pub mod retry;
+pub mod timing;
+pub use timing::Timings;
+pub mod async_challenge;
+pub use async_challenge::{AsyncHackatticChallenge, BoxFuture};
+pub mod credentials;
+pub mod registry;
+pub use registry::Registry;
+pub use hackattic_derive::hackattic_challenge;
+pub mod runner;
+pub use runner::Runner;
+pub mod mock_server;
 /* modules corresponding to specific challenges */
-pub mod visual_basic_math;
+pub mod backup_restore;
+pub mod collision_course;
+pub mod dns_server;
 pub mod face_detect;
+pub mod global_presence;
+pub mod help_me_unpack;
+pub mod mini_miner;
+pub mod password_hashing;
+pub mod redis;
+pub mod tales_of_ssl;
+pub mod visual_basic_math;
+pub mod websocket_chit_chat;
 
 
-lazy_static! {
-    static ref ACCESS_TOKEN: String = {
-        let token = std::env::var("HACKATTIC_ACCESS_TOKEN");
-        if token.is_err() {
-            panic!("Missing HACKATTIC_ACCESS_TOKEN environment variable.")
-        }
-        token.unwrap_or("MISSING_HACKATTIC_ACCESS_TOKEN".to_owned())
+/// A client using the proxy settings of the current `Config`
+pub fn make_reqwest_client() -> Result<reqwest::Client, Error>  {
+    let client = Config::current()?.proxy.client_builder()?.build()?;
+    Ok(client)
+}
+
+#[derive(Debug, Fail)]
+#[fail(display = "solution to \"{}\" failed its own verification, not submitting it(use --force to submit anyway): {}", challenge, reason)]
+pub struct VerificationFailed {
+    pub challenge: String,
+    pub reason: String
+}
+
+/// Runs a challenge's `verify`, failing with `VerificationFailed` unless `force` is set.
+pub fn check_verification(challenge: &str, verified: Result<(), Error>, force: bool) -> Result<(), Error> {
+    match verified {
+        Ok(()) => Ok(()),
+        Err(e) if force => {
+            warn!("solution to \"{}\" failed its own verification, submitting anyway: {}", challenge, e);
+            Ok(())
+        },
+        Err(e) => Err(VerificationFailed { challenge: challenge.to_owned(), reason: e.to_string() }.into())
+    }
+}
+
+/// Fails unless the solution was accepted, with a `HackatticError` telling e.g a rejection
+/// apart from being rate limited, so that each gets its own exit code.
+pub fn check_outcome(result: SubmissionResult) -> Result<SubmissionResult, Error> {
+    let status = result.status;
+    let error = match result.outcome {
+        Outcome::Accepted => return Ok(result),
+        Outcome::Rejected(ref reason) => HackatticError::Rejected { reason: reason.clone() },
+        Outcome::Refused(ref reason) => HackatticError::Refused { status, reason: reason.clone() },
+        Outcome::RateLimited => HackatticError::RateLimited { status },
+        Outcome::Malformed => HackatticError::MalformedResponse { status, body: result.raw.clone() }
     };
+    Err(error.into())
 }
 
+/// The steps of a challenge run besides fetching, solving and submitting themselves: timing
+/// each phase, categorizing failures, checking the solution and recording the run's history
+/// and fixture. Shared by `HackatticChallenge::process_challenge` and its async counterpart.
+pub struct ChallengeRun {
+    challenge: &'static str,
+    config: Arc<Config>,
+    timings: Timings,
+    fixture: Option<Fixture>
+}
 
-pub fn make_reqwest_client() -> Result<reqwest::Client, Error>  {
-    let mut builder = reqwest::Client::builder();
-    if std::env::args().find(|a| a.to_lowercase() == "fiddler").is_some() {
-        builder.proxy(reqwest::Proxy::https("http://127.0.0.1:8888")?);
+impl ChallengeRun {
+    pub fn new(challenge: &'static str, config: Arc<Config>) -> ChallengeRun {
+        ChallengeRun {
+            challenge,
+            config,
+            timings: Timings::default(),
+            fixture: None
+        }
+    }
+
+    /// Takes the fetched problem JSON, recording the run if fetching failed
+    pub fn fetched(&mut self, problem_json: Result<serde_json::Value, Error>, elapsed: Duration) -> Result<serde_json::Value, Error> {
+        self.timings.fetch = Some(elapsed);
+        match problem_json.map_err(|e| HackatticError::network("fetching the problem", e)) {
+            Ok(problem_json) => {
+                self.fixture = Some(Fixture::new(self.challenge, problem_json.clone()));
+                Ok(problem_json)
+            },
+            Err(e) => {
+                self.record(Err(&e))?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Takes the problem and the solver's solution for it, failing if solving took past the
+    /// deadline or the solution fails `verify`. Failures are recorded.
+    pub fn solved<P, S, V>(&mut self, solved: Result<(P, S), Error>, elapsed: Duration, verify: V) -> Result<S, Error>
+        where S: serde::Serialize + Debug, V: FnOnce(&P, &S) -> Result<(), Error>
+    {
+        self.timings.solve = Some(elapsed);
+        let (challenge, deadline, force) = (self.challenge, self.config.solve_deadline(), self.config.force);
+        let solution = solved.and_then(|(problem, solution)| {
+            timing::check_deadline(challenge, elapsed, deadline)?;
+            check_verification(challenge, verify(&problem, &solution), force)?;
+            Ok(solution)
+        }).map_err(HackatticError::solver);
+        let solution = match solution {
+            Ok(solution) => solution,
+            Err(e) => {
+                info!("timings: {}", self.timings);
+                self.record(Err(&e))?;
+                return Err(e);
+            }
+        };
+        info!("got solution: {:?}", solution);
+        if let Some(ref mut fixture) = self.fixture {
+            fixture.solution = Some(serde_json::to_value(&solution)?);
+        }
+        Ok(solution)
+    }
+
+    /// Takes the server's response to the submission and records the run
+    pub fn submitted(mut self, response: Result<SubmissionResult, Error>, elapsed: Duration) -> Result<Option<SubmissionResult>, Error> {
+        self.timings.submit = Some(elapsed);
+        info!("timings: {}", self.timings);
+        let timings = self.timings;
+        let response = response
+            .map_err(|e| HackatticError::network("submitting the solution", e))
+            .map(|response| SubmissionResult { timings, ..response });
+        if let Ok(ref response) = response {
+            info!("got response: {}", response);
+            if let Some(ref mut fixture) = self.fixture {
+                fixture.response = Some(response.raw.clone());
+            }
+        }
+        self.record(response.as_ref())?;
+        response.and_then(check_outcome).map(Some)
+    }
+
+    // the fixture is saved even if solving fails, that's when it's most useful
+    fn record(&self, result: Result<&SubmissionResult, &Error>) -> Result<(), Error> {
+        let problem = self.fixture.as_ref().map(|fixture| &fixture.problem);
+        let solution = self.fixture.as_ref().and_then(|fixture| fixture.solution.as_ref());
+        history::record(&self.config, &history::Run::new(self.challenge, problem, solution, &self.timings, result, self.config.playground));
+        if let (&Some(ref dir), &Some(ref fixture)) = (&self.config.record_dir, &self.fixture) {
+            let path = fixture.save(dir)?;
+            info!("recorded fixture to {}", path.display());
+        }
+        Ok(())
     }
-    let client = builder.build()?;
-    Ok(client)
 }
 
 pub trait HackatticChallenge {
@@ -48,35 +226,89 @@ pub trait HackatticChallenge {
     type Solution;
     fn make_solution(problem: &Self::Problem) -> Result<Self::Solution, Error>;
     fn challenge_name() -> &'static str;
+    /// Checks a solution locally before it's submitted, failing with the reason it's wrong.
+    /// By default every solution passes.
+    fn verify(_problem: &Self::Problem, _solution: &Self::Solution) -> Result<(), Error> {
+        Ok(())
+    }
+    /// Fetches the problem JSON as-is, so it can also be recorded
+    fn get_problem_json(client: &mut reqwest::Client) -> Result<serde_json::Value, Error> {
+        let config = Config::current()?;
+        let url = config.authenticated_url(Self::challenge_name(), "problem")?;
+        config.retry.retry("fetching the problem", || {
+            let problem_json = client
+                .get(&url)
+                .send()?
+                .error_for_status()?;
+            let problem = serde_json::from_reader(problem_json)?;
+            Ok(problem)
+        })
+    }
     fn get_problem(client: &mut reqwest::Client) -> Result<Self::Problem, Error>
         where Self::Problem : serde::de::DeserializeOwned 
     {
-        let problem_json = client
-            .get(&format!("https://hackattic.com/challenges/{}/problem?access_token={}", Self::challenge_name(), &*ACCESS_TOKEN))
-            .send()?;
-        let problem = serde_json::from_reader(problem_json)?;
+        let problem = serde_json::from_value(Self::get_problem_json(client)?)?;
         Ok(problem)
     }
-    fn send_solution(solution: &Self::Solution, client: &mut reqwest::Client) -> Result<String, Error>
+    fn send_solution(solution: &Self::Solution, client: &mut reqwest::Client) -> Result<SubmissionResult, Error>
         where Self::Solution : serde::Serialize {
-        let mut response = client.post(&format!("https://hackattic.com/challenges/{}/solve?access_token={}", Self::challenge_name(), &*ACCESS_TOKEN))
+        let config = Config::current()?;
+        let url = config.authenticated_url(Self::challenge_name(), "solve")?;
+        let retryable = if config.playground { retry::is_transient } else { retry::is_resubmittable };
+        config.retry.retry_if("submitting the solution", retryable, || {
+            let mut response = client.post(&url)
                 .json(solution)
                 .send()?;
-            Ok(format!("{}", response.text()?))
-        }
+            let status = response.status().as_u16();
+            let body = response.text()?;
+            if status >= 500 {
+                return Err(retry::ServerError { status, body }.into());
+            }
+            Ok(SubmissionResult::from_response(status, body, config.playground))
+        })
+    }
 
-    fn process_challenge() -> Result<(), Error>
-        where Self::Problem : serde::de::DeserializeOwned + Debug, Self::Solution : serde::Serialize + Debug
+    /// Fetches, solves and submits a problem. Solving is aborted once the configured deadline
+    /// passes, see `timing::solve_within`. Returns `None` when nothing was
+    /// submitted, i.e when replaying a fixture, and fails unless the solution was
+    /// accepted, see `check_outcome`.
+    fn process_challenge() -> Result<Option<SubmissionResult>, Error>
+        where Self: Sized + 'static,
+              Self::Problem : serde::de::DeserializeOwned + Debug + Send + 'static,
+              Self::Solution : serde::Serialize + Debug + Send + 'static
     {
-        println!("processing challenge \"{}\"", Self::challenge_name());
+        let _span = logging::span(Self::challenge_name());
+        info!("processing challenge \"{}\"", Self::challenge_name());
+        let config = Config::current()?;
+        if let Some(ref path) = config.replay {
+            let solution = fixtures::replay::<Self>(path)?;
+            info!("replayed {}, got solution: {:?}", path.display(), solution);
+            return Ok(None);
+        }
         let mut client = make_reqwest_client()?;
-        let problem = Self::get_problem(&mut client)?;
-        println!("got problem: {:?}", problem);
-        let solution = Self::make_solution(&problem)?;
-        println!("got solution: {:?}", solution);
-        let response = Self::send_solution(&solution, &mut client)?;
-        println!("got response: {}", response);
-        Ok(())
+        let mut run = ChallengeRun::new(Self::challenge_name(), config.clone());
+        let (problem_json, fetch_time) = timing::measure(|| {
+            let _phase = logging::span("fetch");
+            Self::get_problem_json(&mut client)
+        });
+        let problem_json = run.fetched(problem_json, fetch_time)?;
+        let solution = {
+            let _phase = logging::span("solve");
+            let deadline = config.solve_deadline();
+            let (solved, solve_time) = timing::measure(|| error::deserialize_problem(problem_json).and_then(|problem: Self::Problem| {
+                debug!("got problem: {:?}", problem);
+                timing::solve_within(Self::challenge_name(), deadline, move || {
+                    let solution = Self::make_solution(&problem)?;
+                    Ok((problem, solution))
+                })
+            }));
+            run.solved(solved, solve_time, Self::verify)?
+        };
+        let (response, submit_time) = timing::measure(|| {
+            let _phase = logging::span("submit");
+            Self::send_solution(&solution, &mut client)
+        });
+        run.submitted(response, submit_time)
     }
 
 }
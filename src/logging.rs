@@ -0,0 +1,145 @@
+//! Logger setup shared by every binary, and spans which tag log records with
+//! the challenge and phase(fetch, solve, submit) they were logged in.
+
+use env_logger::Builder;
+use env_logger::fmt::{Color, Formatter};
+use failure::Error;
+use log::{Level, LevelFilter, Record};
+use std::cell::RefCell;
+use std::io::Write;
+use std::str::FromStr;
+use std::time::Instant;
+use super::Config;
+use super::timing::format_duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per record, e.g for shipping logs of nightly runs
+    Json
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        LogFormat::Text
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = Error;
+    fn from_str(format: &str) -> Result<LogFormat, Error> {
+        match format {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => bail!("unknown log format \"{}\", expected \"text\" or \"json\"", format)
+        }
+    }
+}
+
+thread_local! {
+    static SPANS: RefCell<Vec<String>> = RefCell::new(Vec::new());
+}
+
+/// Tags the records logged on this thread until dropped, at which point the span's
+/// duration is logged.
+pub struct Span {
+    name: String,
+    start: Instant
+}
+
+pub fn span<S: Into<String>>(name: S) -> Span {
+    let name = name.into();
+    SPANS.with(|spans| spans.borrow_mut().push(name.clone()));
+    Span {
+        name,
+        start: Instant::now()
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        debug!("{} took {}", self.name, format_duration(self.start.elapsed()));
+        SPANS.with(|spans| spans.borrow_mut().pop());
+    }
+}
+
+/// The spans entered on this thread, outermost first
+pub fn current_spans() -> Vec<String> {
+    SPANS.with(|spans| spans.borrow().clone())
+}
+
+/// Tags the records logged on this thread with `spans`(e.g from `current_spans` of the
+/// thread that spawned it), without logging their durations.
+pub fn inherit_spans(spans: Vec<String>) {
+    SPANS.with(|current| *current.borrow_mut() = spans);
+}
+
+fn format_text(buf: &mut Formatter, record: &Record) -> ::std::io::Result<()> {
+    let mut level_style = buf.style();
+    match record.level() {
+        Level::Error => level_style.set_color(Color::Red).set_bold(true),
+        Level::Warn => level_style.set_color(Color::Yellow).set_bold(true),
+        Level::Info => level_style.set_color(Color::Green),
+        Level::Debug => level_style.set_color(Color::Blue),
+        Level::Trace => level_style.set_color(Color::Magenta)
+    };
+    let spans = current_spans();
+    if spans.is_empty() {
+        writeln!(buf, "{:>5} {} > {}", level_style.value(record.level()), record.target(), record.args())
+    } else {
+        writeln!(buf, "{:>5} [{}] {} > {}", level_style.value(record.level()), spans.join(":"), record.target(), record.args())
+    }
+}
+
+fn format_json(buf: &mut Formatter, record: &Record) -> ::std::io::Result<()> {
+    let spans = current_spans();
+    let line = json!({
+        "time": buf.timestamp().to_string(),
+        "level": record.level().to_string(),
+        "target": record.target(),
+        "challenge": spans.get(0),
+        "phase": spans.get(1),
+        "message": record.args().to_string()
+    });
+    writeln!(buf, "{}", line)
+}
+
+fn log_level(config: &Config) -> LevelFilter {
+    config.log_level.as_ref().and_then(|level| level.parse().ok()).unwrap_or(LevelFilter::Info)
+}
+
+/// Initializes the global logger. `RUST_LOG` takes precedence over `config.log_level`,
+/// which defaults to logging this crate at info level.
+pub fn init(config: &Config) {
+    #[cfg(windows)]
+    let _ = ::ansi_term::enable_ansi_support();
+    let mut builder = Builder::new();
+    match config.log_format {
+        LogFormat::Text => builder.format(format_text),
+        LogFormat::Json => builder.format(format_json)
+    };
+    match ::std::env::var("RUST_LOG") {
+        Ok(filters) => builder.parse(&filters),
+        Err(_) => builder.filter(Some("hackattic"), log_level(config))
+    };
+    if builder.try_init().is_err() {
+        debug!("logger was already initialized");
+    }
+    if let Some(ref level) = config.log_level {
+        if level.parse::<LevelFilter>().is_err() {
+            warn!("unknown log level \"{}\", using info", level);
+        }
+    }
+}
+
+#[test]
+fn nests_spans() {
+    assert!(current_spans().is_empty());
+    {
+        let _challenge = span("mini_miner");
+        let _phase = span("solve");
+        assert_eq!(current_spans(), vec!["mini_miner", "solve"]);
+    }
+    assert!(current_spans().is_empty());
+}
@@ -0,0 +1,276 @@
+use serde_json::{self, Value};
+use ring::digest::{self, SHA256, digest};
+use failure::Error;
+use num_cpus;
+use std::sync::{mpsc, Arc};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use super::timing::{self, as_secs_f64, format_duration};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Problem {
+    difficulty: usize,
+    block: Block
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Block {
+    data: Vec<Value>,
+    nonce: Option<u64>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Answer {
+    nonce: u64
+}
+
+
+fn hash_block(block: &Block) -> Vec<u8> {
+    let json_block = serde_json::to_string(block).unwrap();
+    let digest = digest(&SHA256, json_block.as_bytes());
+    digest.as_ref().to_owned()
+}
+
+/// The serialized block split around its nonce, so that hashing a nonce doesn't
+/// serialize the whole block again. The prefix is hashed once into a midstate, which
+/// every nonce's hash continues from.
+#[derive(Clone)]
+struct BlockTemplate {
+    midstate: digest::Context,
+    suffix: Vec<u8>
+}
+
+impl BlockTemplate {
+    fn new(block: &Block) -> Result<BlockTemplate, Error> {
+        let mut block = block.clone();
+        block.nonce = None;
+        let json = serde_json::to_string(&block)?;
+        // the nonce is serialized last, so its null is the last one
+        let nonce_at = json.rfind("null").ok_or_else(|| format_err!("no nonce in serialized block {}", json))?;
+        let mut midstate = digest::Context::new(&SHA256);
+        midstate.update(json[..nonce_at].as_bytes());
+        Ok(BlockTemplate {
+            midstate,
+            suffix: json[nonce_at + "null".len()..].as_bytes().to_owned()
+        })
+    }
+
+    fn hash(&self, nonce: u64) -> digest::Digest {
+        let mut digits = [0u8; 20];
+        let mut context = self.midstate.clone();
+        context.update(format_nonce(nonce, &mut digits));
+        context.update(&self.suffix);
+        context.finish()
+    }
+}
+
+/// Writes the decimal digits of `nonce` into `buf` without allocating, as serde_json would
+fn format_nonce(mut nonce: u64, buf: &mut [u8; 20]) -> &[u8] {
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (nonce % 10) as u8;
+        nonce /= 10;
+        if nonce == 0 {
+            return &buf[start..];
+        }
+    }
+}
+
+/// A found nonce, and the work it took to find it
+struct Mined {
+    nonce: u64,
+    hashes: u64,
+    elapsed: Duration
+}
+
+impl Mined {
+    fn hash_rate(&self) -> f64 {
+        self.hashes as f64 / as_secs_f64(self.elapsed).max(1e-9)
+    }
+}
+
+// how many hashes a worker does between updating the shared count
+const HASH_COUNT_BATCH: usize = 4096;
+
+/// Searches for a nonce on `threads` threads, each trying every `threads`th nonce
+/// until any of them finds one, or until `cancelled` is set.
+fn mine(problem: &Problem, threads: usize, cancelled: Arc<AtomicBool>) -> Result<Mined, Error> {
+    let threads = threads.max(1);
+    let template = BlockTemplate::new(&problem.block)?;
+    let found = Arc::new(AtomicBool::new(false));
+    let hashes = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = mpsc::channel();
+    let start = Instant::now();
+    let workers = (0..threads).map(|worker| {
+        let template = template.clone();
+        let difficulty = problem.difficulty;
+        let (found, cancelled, hashes, sender) = (found.clone(), cancelled.clone(), hashes.clone(), sender.clone());
+        thread::spawn(move || {
+            let mut nonce = worker as u64;
+            let mut count = 0;
+            while !found.load(Ordering::Relaxed) && !cancelled.load(Ordering::Relaxed) {
+                count += 1;
+                if test_hash(template.hash(nonce).as_ref(), difficulty) {
+                    found.store(true, Ordering::Relaxed);
+                    let _ = sender.send(nonce);
+                    break;
+                }
+                if count == HASH_COUNT_BATCH {
+                    hashes.fetch_add(count, Ordering::Relaxed);
+                    count = 0;
+                }
+                nonce += threads as u64;
+            }
+            hashes.fetch_add(count, Ordering::Relaxed);
+        })
+    }).collect::<Vec<_>>();
+    drop(sender);
+    for worker in workers {
+        worker.join().map_err(|_| format_err!("a mining thread panicked"))?;
+    }
+    // several workers may have found one before noticing the others did
+    let nonce = match receiver.iter().min() {
+        Some(nonce) => nonce,
+        None if cancelled.load(Ordering::Relaxed) => bail!("mining was cancelled after {} hashes", hashes.load(Ordering::Relaxed)),
+        None => bail!("no nonce was found")
+    };
+    Ok(Mined {
+        nonce,
+        hashes: hashes.load(Ordering::Relaxed) as u64,
+        elapsed: start.elapsed()
+    })
+}
+
+fn test_hash(hash: &[u8], mut difficulty: usize) -> bool {
+    let mut index = 0;
+    while difficulty > 8usize && index < hash.len() - 1 {
+        if hash[index] != 0 {
+            return false;
+        }
+        difficulty -= 8usize;
+        index += 1;
+    }
+    hash[index].leading_zeros() as usize >= difficulty
+}
+
+
+#[hackattic_challenge(name = "mini_miner", verify = "verify_nonce")]
+fn solve(problem: &Problem) -> Result<Answer, Error> {
+    let mined = mine(problem, num_cpus::get(), timing::cancellation())?;
+    info!("found nonce {} after {} hashes in {}({:.0} hashes/s)",
+          mined.nonce, mined.hashes, format_duration(mined.elapsed), mined.hash_rate());
+    Ok(Answer {
+        nonce: mined.nonce
+    })
+}
+
+fn verify_nonce(problem: &Problem, solution: &Answer) -> Result<(), Error> {
+    let mut block = problem.block.clone();
+    block.nonce = Some(solution.nonce);
+    let block_hash = hash_block(&block);
+    if !test_hash(&block_hash, problem.difficulty) {
+        bail!("hash {} of nonce {} doesn't have {} leading zero bits",
+              ::hex::encode(&block_hash), solution.nonce, problem.difficulty);
+    }
+    Ok(())
+}
+
+#[test]
+fn can_deserialize_problem() {
+    let json = json!({
+          "difficulty": 13,
+          "block": {
+            "nonce": null,
+            "data": [
+              [
+                "a65a7af80a0881c3b0b0f168e853a1fb",
+                -64
+              ],
+              [
+                "4e6d80dd9b3f83bc51b4827c48ee9d31",
+                -12
+              ],
+              [
+                "b991c65ba20f09823ae4be755fc3da8b",
+                22
+              ],
+              [
+                "67d609c670df18f4940e2feeb6680605",
+                12
+              ],
+              [
+                "36719eb2d89c5ae68131e1c68554f3c2",
+                9
+              ]
+            ]
+          }     
+    });
+    serde_json::from_value::<Problem>(json).unwrap();
+}
+
+#[test]
+fn hashing_is_expected() {
+    let block = Block {
+        nonce: Some(45), data: Vec::new()
+    };
+    let digest = hash_block(&block);
+    let expected = ::ring::test::from_hex("00d696db487caf06a2f2a8099479577c3785c37b3d8a77dc413cfb19ec2e0141").unwrap();
+    assert_eq!(digest, expected, "SHA256 digest of block should match the expected one");
+    assert_eq!(test_hash(&digest, 8), true, "block digest should test positively for a difficulty of 8");
+}
+
+#[test]
+fn verifies_nonce() {
+    let problem = Problem {
+        difficulty: 8,
+        block: Block { nonce: None, data: Vec::new() }
+    };
+    assert!(verify_nonce(&problem, &Answer { nonce: 45 }).is_ok());
+    assert!(verify_nonce(&problem, &Answer { nonce: 44 }).is_err());
+}
+
+#[test]
+fn mines_on_several_threads() {
+    let problem = Problem {
+        difficulty: 12,
+        block: Block { nonce: None, data: vec![json!(["a65a7af80a0881c3b0b0f168e853a1fb", -64])] }
+    };
+    let mined = mine(&problem, 4, Arc::new(AtomicBool::new(false))).unwrap();
+    assert!(verify_nonce(&problem, &Answer { nonce: mined.nonce }).is_ok());
+    assert!(mined.hashes > 0);
+    // a single thread tries nonces in order, so it finds the smallest one
+    let single = mine(&problem, 1, Arc::new(AtomicBool::new(false))).unwrap();
+    assert!(single.nonce <= mined.nonce);
+}
+
+#[test]
+fn stops_mining_when_cancelled() {
+    // no hash has this many leading zero bits, so only cancelling stops it
+    let problem = Problem {
+        difficulty: 256,
+        block: Block { nonce: None, data: Vec::new() }
+    };
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let canceller = cancelled.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        canceller.store(true, Ordering::Relaxed);
+    });
+    assert!(mine(&problem, 2, cancelled).is_err());
+}
+
+#[test]
+fn template_hashes_like_block() {
+    // long enough data that the prefix spans several SHA-256 blocks
+    let data = (0..10).map(|i| json!([format!("{:032x}", i * 7919), -i])).collect::<Vec<_>>();
+    for data in vec![Vec::new(), data] {
+        let mut block = Block { data, nonce: None };
+        let template = BlockTemplate::new(&block).unwrap();
+        for &nonce in [0, 9, 10, 45, 123456789, ::std::u64::MAX].iter() {
+            block.nonce = Some(nonce);
+            assert_eq!(template.hash(nonce).as_ref(), &hash_block(&block)[..], "nonce {}", nonce);
+        }
+    }
+}
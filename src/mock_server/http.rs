@@ -0,0 +1,106 @@
+//! Just enough HTTP/1.1 to serve JSON to `reqwest`: one request per connection,
+//! bodies are read via `Content-Length` and the connection is closed after responding.
+
+use failure::Error;
+use serde_json::{self, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub body: Vec<u8>
+}
+
+impl Request {
+    pub fn read_from(stream: &TcpStream) -> Result<Request, Error> {
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().ok_or(format_err!("empty request line"))?.to_owned();
+        let target = parts.next().ok_or(format_err!("request line without a target"))?;
+        let (path, query) = match target.find('?') {
+            Some(index) => (&target[..index], parse_query(&target[index + 1..])),
+            None => (target, HashMap::new())
+        };
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header)?;
+            let header = header.trim();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(index) = header.find(':') {
+                if header[..index].trim().eq_ignore_ascii_case("content-length") {
+                    content_length = header[index + 1..].trim().parse()?;
+                }
+            }
+        }
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body)?;
+        Ok(Request {
+            method,
+            path: path.to_owned(),
+            query,
+            body
+        })
+    }
+
+    pub fn json_body(&self) -> Result<Value, Error> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.find('=') {
+            Some(index) => (pair[..index].to_owned(), pair[index + 1..].to_owned()),
+            None => (pair.to_owned(), String::new())
+        })
+        .collect()
+}
+
+pub struct Response {
+    pub status: u16,
+    pub body: Value
+}
+
+impl Response {
+    pub fn json(status: u16, body: Value) -> Response {
+        Response { status, body }
+    }
+
+    pub fn write_to(&self, mut stream: &TcpStream) -> Result<(), Error> {
+        let body = serde_json::to_vec(&self.body)?;
+        write!(stream, "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+               self.status, reason_phrase(self.status), body.len())?;
+        stream.write_all(&body)?;
+        stream.flush()?;
+        Ok(())
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error"
+    }
+}
+
+#[test]
+fn can_parse_query() {
+    let query = parse_query("access_token=abc&playground=1&flag");
+    assert_eq!(query["access_token"], "abc");
+    assert_eq!(query["playground"], "1");
+    assert_eq!(query["flag"], "");
+}
@@ -0,0 +1,173 @@
+//! A local stand-in for the Hackattic API, serving `/challenges/{name}/problem`
+//! and checking `/challenges/{name}/solve` for the challenges in `problems`.
+//! Point the framework at it by setting `Config::base_url` to `MockServer::base_url`.
+
+use failure::Error;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::{TcpListener, TcpStream, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub mod http;
+pub mod problems;
+pub mod rdb;
+
+use self::http::{Request, Response};
+use self::problems::{Checker, Generator, Random};
+
+/// Outcome of a solution submitted to the mock server
+#[derive(Debug, Clone, PartialEq)]
+pub struct Submission {
+    pub challenge: String,
+    pub solution: Value,
+    /// `None` if the solution was accepted
+    pub rejection: Option<String>
+}
+
+struct State {
+    generators: HashMap<&'static str, Generator>,
+    random: Random,
+    /// the checker of the last problem handed out per challenge
+    pending: Mutex<HashMap<String, Checker>>,
+    submissions: Mutex<Vec<Submission>>
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<State>
+}
+
+impl MockServer {
+    /// Binds to `addr`(use port 0 for any free port) and starts serving on a background thread.
+    pub fn start(addr: &str) -> Result<MockServer, Error> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(State {
+            generators: problems::generators().into_iter().collect(),
+            random: Random::new(),
+            pending: Mutex::new(HashMap::new()),
+            submissions: Mutex::new(Vec::new())
+        });
+        let server_state = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let state = server_state.clone();
+                        thread::spawn(move || handle_connection(&state, stream));
+                    },
+                    Err(e) => error!("mock server failed to accept a connection: {}", e)
+                }
+            }
+        });
+        info!("mock server listening on {}", addr);
+        Ok(MockServer { addr, state })
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn challenge_names(&self) -> Vec<&'static str> {
+        let mut names = self.state.generators.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Every solution submitted so far, in order
+    pub fn submissions(&self) -> Vec<Submission> {
+        self.state.submissions.lock().unwrap().clone()
+    }
+}
+
+fn handle_connection(state: &State, stream: TcpStream) {
+    let response = match Request::read_from(&stream) {
+        Ok(request) => route(state, &request),
+        Err(e) => Response::json(400, json!({ "error": e.to_string() }))
+    };
+    if let Err(e) = response.write_to(&stream) {
+        warn!("mock server couldn't write a response: {}", e);
+    }
+}
+
+fn route(state: &State, request: &Request) -> Response {
+    let segments = request.path.trim_matches('/').split('/').collect::<Vec<_>>();
+    let (name, endpoint) = match segments.as_slice() {
+        &["challenges", name, endpoint] => (name, endpoint),
+        _ => return Response::json(404, json!({ "error": "not found" }))
+    };
+    if request.query.get("access_token").map_or(true, |token| token.is_empty()) {
+        return Response::json(401, json!({ "error": "missing access_token" }));
+    }
+    let generator = match state.generators.get(name) {
+        Some(generator) => generator,
+        None => return Response::json(404, json!({ "error": format!("unknown challenge {}", name) }))
+    };
+    match (request.method.as_str(), endpoint) {
+        ("GET", "problem") => match generator(&state.random) {
+            Ok(generated) => {
+                state.pending.lock().unwrap().insert(name.to_owned(), generated.checker);
+                Response::json(200, generated.problem)
+            },
+            Err(e) => Response::json(500, json!({ "error": e.to_string() }))
+        },
+        ("POST", "solve") => solve(state, name, request),
+        _ => Response::json(405, json!({ "error": "method not allowed" }))
+    }
+}
+
+fn solve(state: &State, name: &str, request: &Request) -> Response {
+    let solution = match request.json_body() {
+        Ok(solution) => solution,
+        Err(e) => return Response::json(400, json!({ "error": format!("malformed solution: {}", e) }))
+    };
+    // like the real thing, playground submissions don't use up the problem
+    let playground = request.query.get("playground").map_or(false, |value| value == "1");
+    let rejection = {
+        let mut pending = state.pending.lock().unwrap();
+        let rejection = match pending.get(name) {
+            Some(checker) => checker(&solution).err(),
+            None => return Response::json(400, json!({ "error": "no problem was fetched for this challenge" }))
+        };
+        if !playground {
+            pending.remove(name);
+        }
+        rejection
+    };
+    let mut response = match rejection {
+        None => json!({ "result": "passed" }),
+        Some(ref reason) => json!({ "rejected": reason })
+    };
+    if playground {
+        response["playground"] = json!(true);
+    }
+    state.submissions.lock().unwrap().push(Submission {
+        challenge: name.to_owned(),
+        solution,
+        rejection
+    });
+    Response::json(200, response)
+}
+
+#[test]
+fn can_solve_challenges_against_mock_server() {
+    use super::{Config, Registry};
+    let server = MockServer::start("127.0.0.1:0").unwrap();
+    // scoped to this thread, so tests running alongside it don't talk to the mock server
+    let _config = Config::scoped(Config {
+        base_url: server.base_url(),
+        access_token: Some("mock".to_owned()),
+        ..Config::default()
+    });
+    let registry = Registry::with_all_challenges();
+    for name in &["help_me_unpack", "mini_miner", "password_hashing", "tales_of_ssl"] {
+        let result = (registry.get(name).unwrap().run)().unwrap().unwrap();
+        assert!(result.is_accepted(), "{} wasn't accepted: {}", name, result);
+    }
+    let submissions = server.submissions();
+    assert_eq!(submissions.len(), 4);
+    for submission in submissions {
+        assert_eq!(submission.rejection, None, "{} was rejected", submission.challenge);
+    }
+}
@@ -0,0 +1,324 @@
+//! Problem generators and solution checkers for the challenges the mock server knows.
+//! Expected answers are computed independently of the solvers where that's cheap.
+
+use base64;
+use failure::Error;
+use hex;
+use openssl;
+use ring;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_json::{self, Value};
+use super::rdb::{self, RdbEntry, RdbValue};
+use ::floats::{FloatFormat, ReportedFloat, ulps_eq_f32};
+use ::help_me_unpack;
+
+/// Checks a submitted solution, returning the rejection reason on failure.
+pub type Checker = Box<Fn(&Value) -> Result<(), String> + Send>;
+
+pub struct GeneratedProblem {
+    pub problem: Value,
+    pub checker: Checker
+}
+
+pub type Generator = fn(&Random) -> Result<GeneratedProblem, Error>;
+
+/// Every challenge the mock server can generate problems for
+pub fn generators() -> Vec<(&'static str, Generator)> {
+    vec![
+        ("collision_course", collision_course as Generator),
+        ("help_me_unpack", help_me_unpack),
+        ("mini_miner", mini_miner),
+        ("password_hashing", password_hashing),
+        ("tales_of_ssl", tales_of_ssl),
+        ("the_redis_one", the_redis_one)
+    ]
+}
+
+pub struct Random(SystemRandom);
+
+impl Random {
+    pub fn new() -> Self {
+        Random(SystemRandom::new())
+    }
+
+    pub fn bytes(&self, len: usize) -> Vec<u8> {
+        let mut buf = vec![0u8; len];
+        self.0.fill(&mut buf).expect("system RNG failed");
+        buf
+    }
+
+    pub fn u64(&self) -> u64 {
+        self.bytes(8).iter().fold(0, |acc, &byte| (acc << 8) | byte as u64)
+    }
+
+    /// A number in `[low, high)`
+    pub fn range(&self, low: u64, high: u64) -> u64 {
+        low + self.u64() % (high - low)
+    }
+
+    pub fn choose<'a, T>(&self, items: &'a [T]) -> &'a T {
+        &items[self.range(0, items.len() as u64) as usize]
+    }
+
+    pub fn hex_string(&self, len: usize) -> String {
+        hex::encode(self.bytes(len / 2))
+    }
+}
+
+fn field<'a>(solution: &'a Value, name: &str) -> Result<&'a Value, String> {
+    solution.get(name).ok_or_else(|| format!("missing field \"{}\"", name))
+}
+
+fn expect_eq(solution: &Value, name: &str, expected: &Value) -> Result<(), String> {
+    let actual = field(solution, name)?;
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!("wrong {}: expected {}, got {}", name, expected, actual))
+    }
+}
+
+fn help_me_unpack(random: &Random) -> Result<GeneratedProblem, Error> {
+    let int = random.u64() as i32;
+    let uint = random.u64() as u32;
+    let short = random.u64() as i16;
+    let float = (random.u64() as i32) as f32 / 1024.0;
+    let double = (random.u64() as i64) as f64 / 65536.0;
+    let big_endian_double = (random.u64() as i64) as f64 / 3.0;
+
+    // packed with the solver's own layout, so that the two can't disagree on it
+    let bytes = help_me_unpack::pack(&help_me_unpack::Solution {
+        int,
+        uint,
+        short,
+        float: ReportedFloat::new(float, FloatFormat::Widened),
+        double,
+        big_endian_double
+    })?;
+
+    let problem = json!({ "bytes": base64::encode(&bytes) });
+    let checker = move |solution: &Value| {
+        expect_eq(solution, "int", &json!(int))?;
+        expect_eq(solution, "uint", &json!(uint))?;
+        expect_eq(solution, "short", &json!(short))?;
+        // like Hackattic, only accepts a number, though any decimal that reads back as the same f32 will do
+        let actual = field(solution, "float")?;
+        match actual.as_f64() {
+            Some(number) if ulps_eq_f32(number as f32, float, 0) => {},
+            _ => return Err(format!("wrong float: expected {}, got {}", float, actual))
+        }
+        expect_eq(solution, "double", &json!(double))?;
+        expect_eq(solution, "big_endian_double", &json!(big_endian_double))
+    };
+    Ok(GeneratedProblem { problem, checker: Box::new(checker) })
+}
+
+fn leading_zero_bits(hash: &[u8]) -> usize {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros() as usize;
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+fn mini_miner(random: &Random) -> Result<GeneratedProblem, Error> {
+    let difficulty = random.range(8, 13) as usize;
+    let data = (0..random.range(1, 6))
+        .map(|_| json!([random.hex_string(32), random.range(0, 200) as i64 - 100]))
+        .collect::<Vec<_>>();
+    let problem = json!({
+        "difficulty": difficulty,
+        "block": { "data": data.clone(), "nonce": null }
+    });
+    let checker = move |solution: &Value| {
+        let nonce = field(solution, "nonce")?.as_u64().ok_or("nonce isn't a number")?;
+        // keys are serialized in sorted order, matching the order of `Block`'s fields
+        let block = serde_json::to_string(&json!({ "data": data, "nonce": nonce })).unwrap();
+        let hash = ring::digest::digest(&ring::digest::SHA256, block.as_bytes());
+        let zeros = leading_zero_bits(hash.as_ref());
+        if zeros >= difficulty {
+            Ok(())
+        } else {
+            Err(format!("hash has {} leading zero bits, expected at least {}", zeros, difficulty))
+        }
+    };
+    Ok(GeneratedProblem { problem, checker: Box::new(checker) })
+}
+
+fn password_hashing(random: &Random) -> Result<GeneratedProblem, Error> {
+    use ring::{digest, hmac, pbkdf2};
+    let password = random.hex_string(16);
+    let salt = random.bytes(16);
+    let rounds = random.range(1000, 5000) as u32;
+    let (n, r, p) = (128, 4, 8);
+    let scrypt = |password: &[u8], salt: &[u8]| -> Result<Vec<u8>, Error> {
+        let mut out = vec![0u8; 32];
+        openssl::pkcs5::scrypt(password, salt, n, r, p, 4000000000, &mut out)?;
+        Ok(out)
+    };
+
+    let sha256 = digest::digest(&digest::SHA256, password.as_bytes());
+    let hmac = hmac::sign(&hmac::SigningKey::new(&digest::SHA256, &salt), password.as_bytes());
+    let mut pbkdf2_out = vec![0u8; digest::SHA256.output_len];
+    pbkdf2::derive(&digest::SHA256, rounds, &salt, password.as_bytes(), &mut pbkdf2_out);
+    let expected = json!({
+        "sha256": hex::encode(sha256.as_ref()),
+        "hmac": hex::encode(hmac.as_ref()),
+        "pbkdf2": hex::encode(&pbkdf2_out),
+        "scrypt": hex::encode(&scrypt(password.as_bytes(), &salt)?)
+    });
+
+    let problem = json!({
+        "password": password,
+        "salt": base64::encode(&salt),
+        "pbkdf2": { "rounds": rounds, "hash": "sha256" },
+        "scrypt": {
+            "N": n, "r": r, "p": p, "buflen": 32,
+            "_control": hex::encode(&scrypt(b"rosebud", b"pepper")?)
+        }
+    });
+    let checker = move |solution: &Value| {
+        for name in &["sha256", "hmac", "pbkdf2", "scrypt"] {
+            expect_eq(solution, name, &expected[*name])?;
+        }
+        Ok(())
+    };
+    Ok(GeneratedProblem { problem, checker: Box::new(checker) })
+}
+
+fn tales_of_ssl(random: &Random) -> Result<GeneratedProblem, Error> {
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+    use openssl::x509::X509;
+    use openssl::nid;
+
+    let key = PKey::from_rsa(Rsa::generate(2048)?)?;
+    let domain = format!("{}.com", random.hex_string(8));
+    let &(country, code) = random.choose(&[
+        ("Cocos Islands", "CC"),
+        ("Christmas Island", "CX"),
+        ("Tokelau Islands", "TK"),
+        ("Keeling Islands", "CC")
+    ]);
+    let problem = json!({
+        "private_key": base64::encode(&key.private_key_to_der()?),
+        "required_data": {
+            "domain": domain,
+            "serial_number": format!("0x{}", random.hex_string(8)),
+            "country": country
+        }
+    });
+    // openssl 0.9 can't read back the serial number, so it isn't checked
+    let checker = move |solution: &Value| {
+        let certificate = field(solution, "certificate")?.as_str().ok_or("certificate isn't a string")?;
+        let der = base64::decode(certificate).map_err(|e| format!("certificate isn't base64: {}", e))?;
+        let x509 = X509::from_der(&der).map_err(|e| format!("certificate isn't valid DER: {}", e))?;
+        let entry = |id: nid::Nid| x509.subject_name().entries_by_nid(id).next()
+            .and_then(|entry| entry.data().as_utf8().ok())
+            .map(|data| data.to_string());
+        if entry(nid::COMMONNAME).as_ref().map(|s| s.as_str()) != Some(domain.as_str()) {
+            return Err(format!("subject CN isn't {}", domain));
+        }
+        if entry(nid::COUNTRYNAME).as_ref().map(|s| s.as_str()) != Some(code) {
+            return Err(format!("subject C isn't {}", code));
+        }
+        match x509.public_key() {
+            Ok(ref public_key) if public_key.public_eq(&key) => Ok(()),
+            _ => Err("certificate isn't for the given private key".to_owned())
+        }
+    };
+    Ok(GeneratedProblem { problem, checker: Box::new(checker) })
+}
+
+fn collision_course(random: &Random) -> Result<GeneratedProblem, Error> {
+    use openssl::hash::{hash, MessageDigest};
+    let include = random.hex_string(16);
+    let problem = json!({ "include": include });
+    let checker = move |solution: &Value| {
+        let files = field(solution, "files")?.as_array().ok_or("files isn't an array")?
+            .iter()
+            .map(|file| file.as_str().and_then(|file| base64::decode(file).ok()).ok_or("file isn't base64"))
+            .collect::<Result<Vec<_>, _>>()?;
+        if files.len() != 2 || files[0] == files[1] {
+            return Err("expected two different files".to_owned());
+        }
+        if !files.iter().all(|file| file.starts_with(include.as_bytes())) {
+            return Err(format!("files must begin with {:?}", include));
+        }
+        let md5 = |data: &[u8]| hash(MessageDigest::md5(), data).map_err(|e| e.to_string());
+        if md5(&files[0])? == md5(&files[1])? {
+            Ok(())
+        } else {
+            Err("files don't share an MD5".to_owned())
+        }
+    };
+    Ok(GeneratedProblem { problem, checker: Box::new(checker) })
+}
+
+fn the_redis_one(random: &Random) -> Result<GeneratedProblem, Error> {
+    let db_count = random.range(1, 4) as usize;
+    let emoji = ::std::char::from_u32(0x1f600 + random.range(0, 0x50) as u32).unwrap();
+    let emoji_key_value = random.hex_string(8);
+    let expiry_millis = 1_600_000_000_000 + random.range(0, 1_000_000_000);
+    let check_type_of = format!("key_{}", random.hex_string(6));
+    let check_value = match random.range(0, 4) {
+        0 => RdbValue::String(random.hex_string(8)),
+        1 => RdbValue::List(vec![random.hex_string(8), random.hex_string(8)]),
+        2 => RdbValue::Set(vec![random.hex_string(8)]),
+        _ => RdbValue::Hash(vec![(random.hex_string(4), random.hex_string(8))])
+    };
+    let mut expected = json!({
+        "db_count": db_count,
+        "emoji_key_value": emoji_key_value,
+        "expiry_millis": expiry_millis
+    });
+    expected[check_type_of.as_str()] = json!(check_value.type_name());
+
+    let mut databases = (0..db_count).map(|_| Vec::new()).collect::<Vec<_>>();
+    databases[0].push(RdbEntry {
+        key: format!("{}{}", emoji, random.hex_string(4)),
+        value: RdbValue::String(emoji_key_value),
+        expiry_millis: None
+    });
+    databases[db_count - 1].push(RdbEntry {
+        key: random.hex_string(8),
+        value: RdbValue::String(random.hex_string(8)),
+        expiry_millis: Some(expiry_millis)
+    });
+    databases[0].push(RdbEntry {
+        key: check_type_of.clone(),
+        value: check_value,
+        expiry_millis: None
+    });
+    let mut rdb = rdb::write_rdb(&databases);
+    // like the real challenge, the magic header is corrupted
+    rdb[..5].copy_from_slice(&random.bytes(5));
+
+    let problem = json!({
+        "rdb": base64::encode(&rdb),
+        "requirements": { "check_type_of": check_type_of }
+    });
+    let checker = move |solution: &Value| {
+        for (name, value) in expected.as_object().unwrap() {
+            expect_eq(solution, name, value)?;
+        }
+        Ok(())
+    };
+    Ok(GeneratedProblem { problem, checker: Box::new(checker) })
+}
+
+#[test]
+fn counts_leading_zero_bits() {
+    assert_eq!(leading_zero_bits(&[0, 0x0f, 0xff]), 12);
+    assert_eq!(leading_zero_bits(&[0x80]), 0);
+}
+
+#[test]
+fn rejects_incomplete_unpack_solution() {
+    let generated = help_me_unpack(&Random::new()).unwrap();
+    let solution = json!({ "int": 0 });
+    assert!((generated.checker)(&solution).is_err());
+}
@@ -0,0 +1,106 @@
+//! A minimal RDB(version 7) writer, used to generate problems for "the_redis_one".
+//! Only supports what the challenge needs: plain(non-encoded) strings, lists, sets,
+//! hashes and millisecond expiries. See https://rdb.fnordig.de/file_format.html
+
+use byteorder::{LE, WriteBytesExt};
+
+pub enum RdbValue {
+    String(String),
+    List(Vec<String>),
+    Set(Vec<String>),
+    Hash(Vec<(String, String)>)
+}
+
+impl RdbValue {
+    /// The name `TYPE` would return for this value
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            RdbValue::String(_) => "string",
+            RdbValue::List(_) => "list",
+            RdbValue::Set(_) => "set",
+            RdbValue::Hash(_) => "hash"
+        }
+    }
+
+    fn type_code(&self) -> u8 {
+        match *self {
+            RdbValue::String(_) => 0,
+            RdbValue::List(_) => 1,
+            RdbValue::Set(_) => 2,
+            RdbValue::Hash(_) => 4
+        }
+    }
+}
+
+pub struct RdbEntry {
+    pub key: String,
+    pub value: RdbValue,
+    /// Unix timestamp in milliseconds
+    pub expiry_millis: Option<u64>
+}
+
+pub fn write_rdb(databases: &[Vec<RdbEntry>]) -> Vec<u8> {
+    let mut out = b"REDIS0007".to_vec();
+    for (index, entries) in databases.iter().enumerate() {
+        out.push(0xFE);
+        write_length(&mut out, index);
+        for entry in entries {
+            if let Some(expiry) = entry.expiry_millis {
+                out.push(0xFC);
+                out.write_u64::<LE>(expiry).unwrap();
+            }
+            out.push(entry.value.type_code());
+            write_string(&mut out, &entry.key);
+            match entry.value {
+                RdbValue::String(ref value) => write_string(&mut out, value),
+                RdbValue::List(ref values) | RdbValue::Set(ref values) => {
+                    write_length(&mut out, values.len());
+                    for value in values {
+                        write_string(&mut out, value);
+                    }
+                },
+                RdbValue::Hash(ref pairs) => {
+                    write_length(&mut out, pairs.len());
+                    for &(ref field, ref value) in pairs {
+                        write_string(&mut out, field);
+                        write_string(&mut out, value);
+                    }
+                }
+            }
+        }
+    }
+    out.push(0xFF);
+    // a zeroed checksum means "checksum disabled"
+    out.write_u64::<LE>(0).unwrap();
+    out
+}
+
+fn write_length(out: &mut Vec<u8>, length: usize) {
+    if length < 1 << 6 {
+        out.push(length as u8);
+    } else if length < 1 << 14 {
+        out.push(0x40 | (length >> 8) as u8);
+        out.push(length as u8);
+    } else {
+        out.push(0x80);
+        out.extend_from_slice(&[(length >> 24) as u8, (length >> 16) as u8, (length >> 8) as u8, length as u8]);
+    }
+}
+
+fn write_string(out: &mut Vec<u8>, string: &str) {
+    write_length(out, string.len());
+    out.extend_from_slice(string.as_bytes());
+}
+
+#[test]
+fn writes_expected_layout() {
+    let rdb = write_rdb(&[vec![RdbEntry {
+        key: "a".to_owned(),
+        value: RdbValue::String("b".to_owned()),
+        expiry_millis: None
+    }]]);
+    assert_eq!(&rdb[..9], b"REDIS0007");
+    assert_eq!(&rdb[9..15], &[0xFE, 0, 0, 1, b'a', 1][..]);
+    assert_eq!(rdb[15], b'b');
+    assert_eq!(rdb[16], 0xFF);
+}
@@ -1,23 +1,12 @@
-extern crate hackattic;
-#[macro_use]
-extern crate serde_derive;
-extern crate serde;
-extern crate serde_json;
-extern crate base64;
-extern crate hex;
-extern crate ring;
-extern crate reqwest;
-extern crate failure;
-extern crate openssl;
-
 use failure::Error;
-use hackattic::{from_base64,from_hex,as_hex, make_reqwest_client, HackatticChallenge};
+use ring;
+use openssl;
+use super::{Base64, Hex, make_reqwest_client};
 
 #[derive(Deserialize,Debug,Clone)]
 struct Problem {
     password: String,
-    #[serde(deserialize_with = "from_base64")]
-    salt: Vec<u8>,
+    salt: Base64,
     pbkdf2: PBKDF2,
     scrypt: Scrypt
 }
@@ -64,8 +53,7 @@ struct Scrypt {
     r: u64,
     p: u64,
     buflen: usize,
-    #[serde(deserialize_with="from_hex")]
-    _control: Vec<u8>
+    _control: Hex
 }
 
 impl Scrypt {
@@ -80,14 +68,10 @@ impl Scrypt {
 // Fields are in hex notation(without the 0x)
 #[derive(Serialize,Debug,Clone)]
 struct Answer {
-    #[serde(serialize_with="as_hex")]
-    sha256: Vec<u8>,
-    #[serde(serialize_with="as_hex")]
-    hmac: Vec<u8>,
-    #[serde(serialize_with="as_hex")]
-    pbkdf2: Vec<u8>,
-    #[serde(serialize_with="as_hex")]
-    scrypt: Vec<u8>
+    sha256: Hex,
+    hmac: Hex,
+    pbkdf2: Hex,
+    scrypt: Hex
 }
 
 #[derive(Serialize,Deserialize,Debug,Copy,Clone)]
@@ -103,39 +87,37 @@ impl Hash {
     }
 }
 
-struct PasswordHashing;
-impl HackatticChallenge for PasswordHashing {
-    type Problem = Problem;
-    type Solution = Answer;
-    fn challenge_name() -> &'static str {
-        "password_hashing"
-    }
-    fn make_solution(req: &Problem) -> Result<Answer, Error> {
-        let answer = Answer {
-            sha256: req.digest_sha256(),
-            hmac: req.hmac()?,
-            pbkdf2: req.derive_pbkdf2(),
-            scrypt: req.derive_scrypt()?
-        };
-        Ok(answer)
-    }
+#[hackattic_challenge(name = "password_hashing", verify = "verify_solution")]
+fn solve(req: &Problem) -> Result<Answer, Error> {
+    let answer = Answer {
+        sha256: Hex(req.digest_sha256()),
+        hmac: Hex(req.hmac()?),
+        pbkdf2: Hex(req.derive_pbkdf2()),
+        scrypt: Hex(req.derive_scrypt()?)
+    };
+    Ok(answer)
 }
 
-fn main() {
-    PasswordHashing::process_challenge().unwrap();
+/// `_control` is the scrypt of "rosebud" salted with "pepper", so it checks the scrypt parameters are used right
+fn verify_solution(req: &Problem, _answer: &Answer) -> Result<(), Error> {
+    let control = req.scrypt.derive(b"pepper", b"rosebud")?;
+    if control[..] != req.scrypt._control[..] {
+        bail!("scrypt of the control password is {}, expected {}", ::hex::encode(&control), req.scrypt._control);
+    }
+    Ok(())
 }
 
 #[test]
 fn can_scrypt_hash() {
     let password = "rosebud";
     let salt = "pepper";
-    let control = hex::decode("b19a18ea8a50a861d08eb94be602f6cbfe67ab98d2021400a3b83fbe3b8ba698").unwrap();
+    let control = ::hex::decode("b19a18ea8a50a861d08eb94be602f6cbfe67ab98d2021400a3b83fbe3b8ba698").unwrap();
     let scrypt = Scrypt {
         n: 128,
         r: 4,
         p: 8,
         buflen: control.len(),
-        _control: Vec::new()
+        _control: Hex::default()
     };
     let derivation = scrypt.derive(salt.as_bytes(), password.as_bytes()).unwrap();
     assert_eq!(&derivation, &control, "Derived Scrypt key isn't equal to control parameter");
@@ -143,8 +125,9 @@ fn can_scrypt_hash() {
 
 #[test]
 fn can_fetch_and_deserialize_correctly() {
+    use HackatticChallenge;
     let req = PasswordHashing::get_problem(&mut make_reqwest_client().unwrap()).unwrap();
-    let control_bytes = req.scrypt._control;
-    let control_expected = hex::decode("b19a18ea8a50a861d08eb94be602f6cbfe67ab98d2021400a3b83fbe3b8ba698").unwrap();
+    let control_bytes = req.scrypt._control.into_inner();
+    let control_expected = ::hex::decode("b19a18ea8a50a861d08eb94be602f6cbfe67ab98d2021400a3b83fbe3b8ba698").unwrap();
     assert_eq!(control_bytes, control_expected, "should deserialize the control value correctly");
-}
\ No newline at end of file
+}
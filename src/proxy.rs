@@ -0,0 +1,192 @@
+//! Proxy and TLS settings shared by every HTTP client the crate builds, e.g for
+//! routing traffic through an intercepting proxy such as Fiddler or mitmproxy.
+
+use failure::{Error, ResultExt};
+use openssl::x509::X509;
+use reqwest::{self, Certificate, ClientBuilder, Proxy, Url};
+use reqwest::unstable::async;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use tokio_core::reactor::Handle;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    /// Proxy for plain HTTP requests, e.g "http://127.0.0.1:8888"
+    pub http: Option<String>,
+    /// Proxy for HTTPS requests
+    pub https: Option<String>,
+    /// SOCKS5 proxy for all requests, e.g "socks5://127.0.0.1:1080". It isn't supported by
+    /// the HTTP client yet, so setting it in the config fails when the config is loaded.
+    pub socks5: Option<String>,
+    /// Hosts that are connected to directly. An entry also matches its subdomains,
+    /// and "*" matches every host.
+    pub no_proxy: Vec<String>,
+    /// Extra PEM or DER certificates to trust, e.g the root of an intercepting proxy
+    pub ca_certs: Vec<PathBuf>
+}
+
+impl ProxyConfig {
+    /// Reads `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY`, in upper or lower case.
+    /// An HTTP `ALL_PROXY` is used for whichever of HTTP and HTTPS has no proxy of its own.
+    /// Proxies the HTTP client can't use, e.g SOCKS ones, are ignored with a warning rather than
+    /// failing every command, as they're often set for other tools.
+    pub fn apply_env(&mut self) {
+        self.apply_vars(|name| ::std::env::var(name).or_else(|_| ::std::env::var(name.to_lowercase()))
+            .ok().filter(|value| !value.is_empty()));
+    }
+
+    fn apply_vars<F: Fn(&str) -> Option<String>>(&mut self, var: F) {
+        let proxy_var = |name: &str| var(name).filter(|proxy| {
+            let supported = proxy.starts_with("http://") || proxy.starts_with("https://");
+            if !supported {
+                // the config is loaded before logging is set up, so this can't use warn!
+                eprintln!("warning: ignoring {}={}, only http:// and https:// proxies are supported", name, proxy);
+            }
+            supported
+        });
+        if let Some(http) = proxy_var("HTTP_PROXY") {
+            self.http = Some(http);
+        }
+        if let Some(https) = proxy_var("HTTPS_PROXY") {
+            self.https = Some(https);
+        }
+        if let Some(all) = proxy_var("ALL_PROXY") {
+            self.http = self.http.take().or_else(|| Some(all.clone()));
+            self.https = self.https.take().or_else(|| Some(all));
+        }
+        if let Some(no_proxy) = var("NO_PROXY") {
+            self.no_proxy = no_proxy.split(',').map(|host| host.trim().to_owned()).filter(|host| !host.is_empty()).collect();
+        }
+    }
+
+    /// Fails on settings the HTTP clients can't use, so that they're reported when the
+    /// config is loaded rather than on the first request.
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some(ref socks5) = self.socks5 {
+            // reqwest 0.8 can only tunnel through HTTP proxies
+            bail!("SOCKS proxy {} isn't supported, run an HTTP-to-SOCKS bridge(e.g privoxy) and set it as the http/https proxy instead", socks5);
+        }
+        for &(scheme, proxy) in &[("http", &self.http), ("https", &self.https)] {
+            if let Some(ref proxy) = *proxy {
+                let proxy_url = Url::parse(proxy).with_context(|_| format!("invalid {} proxy {}", scheme, proxy))?;
+                if proxy_url.scheme() != "http" && proxy_url.scheme() != "https" {
+                    bail!("{} proxy {} isn't supported, only HTTP proxies are", scheme, proxy);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether requests to `host` skip the proxy
+    pub fn bypasses(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        self.no_proxy.iter().any(|entry| {
+            let entry = entry.trim_left_matches('.').to_lowercase();
+            entry == "*" || host == entry || host.ends_with(&format!(".{}", entry))
+        })
+    }
+
+    pub fn proxies(&self) -> Result<Vec<Proxy>, Error> {
+        self.validate()?;
+        let mut proxies = Vec::new();
+        for &(scheme, proxy) in &[("http", &self.http), ("https", &self.https)] {
+            if let Some(ref proxy) = *proxy {
+                let proxy_url = Url::parse(proxy).with_context(|_| format!("invalid {} proxy {}", scheme, proxy))?;
+                let config = self.clone();
+                proxies.push(Proxy::custom(move |url| {
+                    let bypassed = url.host_str().map_or(false, |host| config.bypasses(host));
+                    if url.scheme() == scheme && !bypassed {
+                        Some(proxy_url.clone())
+                    } else {
+                        None
+                    }
+                }));
+            }
+        }
+        Ok(proxies)
+    }
+
+    pub fn certificates(&self) -> Result<Vec<Certificate>, Error> {
+        let mut certificates = Vec::new();
+        for path in &self.ca_certs {
+            let mut contents = Vec::new();
+            File::open(path)
+                .and_then(|mut file| file.read_to_end(&mut contents))
+                .with_context(|_| format!("couldn't read CA certificate {}", path.display()))?;
+            if contents.starts_with(b"-----BEGIN") {
+                for cert in X509::stack_from_pem(&contents)? {
+                    certificates.push(Certificate::from_der(&cert.to_der()?)?);
+                }
+            } else {
+                certificates.push(Certificate::from_der(&contents)?);
+            }
+        }
+        Ok(certificates)
+    }
+
+    /// A client builder with the configured proxies and certificates
+    pub fn client_builder(&self) -> Result<ClientBuilder, Error> {
+        let mut builder = reqwest::Client::builder();
+        for proxy in self.proxies()? {
+            builder.proxy(proxy);
+        }
+        for certificate in self.certificates()? {
+            builder.add_root_certificate(certificate);
+        }
+        Ok(builder)
+    }
+
+    pub fn async_client(&self, handle: &Handle) -> Result<async::Client, Error> {
+        let mut builder = async::Client::builder();
+        for proxy in self.proxies()? {
+            builder.proxy(proxy);
+        }
+        for certificate in self.certificates()? {
+            builder.add_root_certificate(certificate);
+        }
+        Ok(builder.build(handle)?)
+    }
+}
+
+#[test]
+fn bypasses_no_proxy_hosts() {
+    let config = ProxyConfig {
+        no_proxy: vec!["localhost".to_owned(), ".internal.net".to_owned()],
+        ..ProxyConfig::default()
+    };
+    assert!(config.bypasses("localhost"));
+    assert!(config.bypasses("files.internal.net"));
+    assert!(!config.bypasses("hackattic.com"));
+}
+
+#[test]
+fn rejects_socks5() {
+    let config = ProxyConfig {
+        socks5: Some("socks5://127.0.0.1:1080".to_owned()),
+        ..ProxyConfig::default()
+    };
+    assert!(config.validate().is_err());
+    assert!(config.proxies().is_err());
+    let config = ProxyConfig {
+        http: Some("socks5://127.0.0.1:1080".to_owned()),
+        ..ProxyConfig::default()
+    };
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn ignores_unsupported_env_proxies() {
+    let mut config = ProxyConfig::default();
+    config.apply_vars(|name| match name {
+        "ALL_PROXY" => Some("socks5://127.0.0.1:1080".to_owned()),
+        "HTTPS_PROXY" => Some("socks5h://127.0.0.1:1080".to_owned()),
+        "HTTP_PROXY" => Some("http://127.0.0.1:8888".to_owned()),
+        _ => None
+    });
+    assert_eq!(config.http, Some("http://127.0.0.1:8888".to_owned()));
+    assert_eq!(config.https, None);
+    assert_eq!(config.socks5, None);
+    assert!(config.validate().is_ok());
+}
@@ -0,0 +1,102 @@
+use failure::Error;
+use serde;
+use std::fmt::Debug;
+use super::{AsyncHackatticChallenge, HackatticChallenge, SubmissionResult};
+
+/// A challenge that can be looked up and ran by its Hackattic name.
+#[derive(Clone, Copy)]
+pub struct Entry {
+    pub name: &'static str,
+    pub run: fn() -> Result<Option<SubmissionResult>, Error>,
+    /// Whether the challenge must not run alongside others, e.g because it binds a fixed port
+    pub exclusive: bool
+}
+
+/// Holds every challenge known to the dispatcher. Each challenge module
+/// exposes a `register` function which adds its `HackatticChallenge` implementor here,
+/// usually generated by `#[hackattic_challenge]`.
+pub struct Registry {
+    entries: Vec<Entry>
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry {
+            entries: Vec::new()
+        }
+    }
+
+    /// Creates a registry containing every challenge implemented by this crate.
+    pub fn with_all_challenges() -> Self {
+        use super::*;
+        let mut registry = Registry::new();
+        backup_restore::register(&mut registry);
+        collision_course::register(&mut registry);
+        dns_server::register(&mut registry);
+        #[cfg(feature = "facedetect")]
+        face_detect::register(&mut registry);
+        global_presence::register(&mut registry);
+        help_me_unpack::register(&mut registry);
+        mini_miner::register(&mut registry);
+        password_hashing::register(&mut registry);
+        redis::register(&mut registry);
+        tales_of_ssl::register(&mut registry);
+        visual_basic_math::register(&mut registry);
+        websocket_chit_chat::register(&mut registry);
+        // keeps every core busy, dns_server marks itself as it serves on a fixed port
+        registry.set_exclusive("mini_miner");
+        registry
+    }
+
+    pub fn register<C: HackatticChallenge + 'static>(&mut self)
+        where C::Problem: serde::de::DeserializeOwned + Debug + Send + 'static,
+              C::Solution: serde::Serialize + Debug + Send + 'static
+    {
+        self.add(Entry {
+            name: C::challenge_name(),
+            run: C::process_challenge,
+            exclusive: false
+        });
+    }
+
+    pub fn register_async<C: AsyncHackatticChallenge>(&mut self) {
+        self.add(Entry {
+            name: C::challenge_name(),
+            run: C::process_challenge,
+            exclusive: false
+        });
+    }
+
+    /// Adds an entry directly, e.g one whose `run` isn't a `HackatticChallenge`
+    pub fn add(&mut self, entry: Entry) {
+        assert!(self.get(entry.name).is_none(), "challenge \"{}\" was registered twice", entry.name);
+        self.entries.push(entry);
+        self.entries.sort_by_key(|entry| entry.name);
+    }
+
+    /// Makes the `Runner` run the challenge on its own, see `Entry::exclusive`
+    pub fn set_exclusive(&mut self, name: &str) {
+        let entry = self.entries.iter_mut().find(|entry| entry.name == name)
+            .unwrap_or_else(|| panic!("challenge \"{}\" isn't registered", name));
+        entry.exclusive = true;
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+}
+
+#[test]
+fn registers_every_challenge_once() {
+    let registry = Registry::with_all_challenges();
+    assert!(registry.get("mini_miner").is_some());
+    assert!(registry.get("help_me_unpack").is_some());
+    assert!(registry.get("no_such_challenge").is_none());
+    assert!(registry.get("serving_dns").unwrap().exclusive);
+    assert!(registry.get("mini_miner").unwrap().exclusive);
+    assert!(!registry.get("help_me_unpack").unwrap().exclusive);
+}
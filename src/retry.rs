@@ -0,0 +1,173 @@
+//! Retrying of requests to Hackattic with exponential backoff. Only failures that
+//! might go away by themselves are retried: network errors and 5xx responses.
+//! A 4xx response means the request itself is wrong, so it is never retried.
+//!
+//! Submitting a solution is only retried on 5xx responses(see `is_resubmittable`), as a
+//! network error may hide a submission that reached Hackattic and already used up an attempt.
+
+use failure::Error;
+use futures::{future, Future};
+use futures::future::{loop_fn, Loop};
+use reqwest;
+use std::io;
+use std::thread;
+use std::time::Duration;
+use tokio_core::reactor::{Handle, Timeout};
+use super::BoxFuture;
+
+#[derive(Debug, Fail)]
+#[fail(display = "server responded with HTTP {}: {}", status, body)]
+pub struct ServerError {
+    pub status: u16,
+    pub body: String
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Including the first attempt, so 1 disables retrying
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Factor the backoff grows by after each failed attempt
+    pub multiplier: f64
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 8000,
+            multiplier: 2.0
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// How long to wait after the `attempt`th(starting at 1) attempt failed
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self.initial_backoff_ms as f64 * self.multiplier.powi(attempt as i32 - 1);
+        Duration::from_millis(backoff.min(self.max_backoff_ms as f64) as u64)
+    }
+
+    /// Whether another attempt should follow the `attempt`th one failing with `error`
+    fn should_retry(&self, what: &str, attempt: u32, error: &Error, retryable: fn(&Error) -> bool) -> bool {
+        if attempt >= self.max_attempts || !retryable(error) {
+            return false;
+        }
+        warn!("{} failed(attempt {}/{}), retrying in {:?}: {}", what, attempt, self.max_attempts, self.backoff(attempt), error);
+        true
+    }
+
+    /// Calls `request` until it succeeds, fails permanently or runs out of attempts,
+    /// sleeping between attempts.
+    pub fn retry<T, F>(&self, what: &str, request: F) -> Result<T, Error>
+        where F: FnMut() -> Result<T, Error>
+    {
+        self.retry_if(what, is_transient, request)
+    }
+
+    /// Like `retry`, but only errors for which `retryable` returns true are retried
+    pub fn retry_if<T, F>(&self, what: &str, retryable: fn(&Error) -> bool, mut request: F) -> Result<T, Error>
+        where F: FnMut() -> Result<T, Error>
+    {
+        let mut attempt = 1;
+        loop {
+            match request() {
+                Err(ref e) if self.should_retry(what, attempt, e, retryable) => thread::sleep(self.backoff(attempt)),
+                result => return result
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Like `retry`, but waits between attempts on the event loop of `handle` instead of sleeping.
+    pub fn retry_async<T, F>(&self, what: &'static str, handle: &Handle, request: F) -> BoxFuture<T>
+        where T: 'static, F: FnMut() -> BoxFuture<T> + 'static
+    {
+        self.retry_async_if(what, handle, is_transient, request)
+    }
+
+    /// See `retry_if`
+    pub fn retry_async_if<T, F>(&self, what: &'static str, handle: &Handle, retryable: fn(&Error) -> bool, request: F) -> BoxFuture<T>
+        where T: 'static, F: FnMut() -> BoxFuture<T> + 'static
+    {
+        let policy = self.clone();
+        let handle = handle.clone();
+        Box::new(loop_fn((1, request), move |(attempt, mut request)| {
+            let policy = policy.clone();
+            let handle = handle.clone();
+            request().then(move |result| -> BoxFuture<Loop<T, (u32, F)>> {
+                match result {
+                    Ok(value) => Box::new(future::ok(Loop::Break(value))),
+                    Err(ref e) if policy.should_retry(what, attempt, e, retryable) => {
+                        match Timeout::new(policy.backoff(attempt), &handle) {
+                            Ok(timeout) => Box::new(timeout.map_err(Error::from)
+                                .map(move |_| Loop::Continue((attempt + 1, request)))),
+                            Err(e) => Box::new(future::err(e.into()))
+                        }
+                    },
+                    Err(e) => Box::new(future::err(e))
+                }
+            })
+        }))
+    }
+}
+
+/// Network errors and 5xx responses are transient, anything else(e.g a 4xx
+/// response or a body that doesn't deserialize) won't be fixed by retrying.
+pub fn is_transient(error: &Error) -> bool {
+    if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+        return !(e.is_client_error() || e.is_serialization() || e.is_redirect());
+    }
+    error.downcast_ref::<ServerError>().is_some() || error.downcast_ref::<io::Error>().is_some()
+}
+
+/// Whether a failed submission can be sent again without risking a second attempt being
+/// counted, i.e the server answered with a 5xx. Playground submissions don't count as
+/// attempts, so they're retried like any other request.
+pub fn is_resubmittable(error: &Error) -> bool {
+    error.downcast_ref::<ServerError>().is_some()
+}
+
+#[test]
+fn backoff_grows_up_to_max() {
+    let policy = RetryPolicy::default();
+    assert_eq!(policy.backoff(1), Duration::from_millis(500));
+    assert_eq!(policy.backoff(2), Duration::from_millis(1000));
+    assert_eq!(policy.backoff(10), Duration::from_millis(8000));
+}
+
+#[test]
+fn retries_only_transient_errors() {
+    let policy = RetryPolicy { initial_backoff_ms: 0, ..RetryPolicy::default() };
+    let mut attempts = 0;
+    let result: Result<(), Error> = policy.retry("fetching", || {
+        attempts += 1;
+        Err(ServerError { status: 502, body: String::new() }.into())
+    });
+    assert!(result.is_err());
+    assert_eq!(attempts, 3);
+
+    attempts = 0;
+    let result: Result<(), Error> = policy.retry("fetching", || {
+        attempts += 1;
+        Err(format_err!("invalid problem"))
+    });
+    assert!(result.is_err());
+    assert_eq!(attempts, 1);
+}
+
+#[test]
+fn resubmits_only_server_errors() {
+    let policy = RetryPolicy { initial_backoff_ms: 0, ..RetryPolicy::default() };
+    let mut attempts = 0;
+    let result: Result<(), Error> = policy.retry_if("submitting", is_resubmittable, || {
+        attempts += 1;
+        Err(io::Error::new(io::ErrorKind::ConnectionReset, "connection reset").into())
+    });
+    assert!(result.is_err());
+    assert_eq!(attempts, 1);
+    assert!(is_resubmittable(&ServerError { status: 503, body: String::new() }.into()));
+}
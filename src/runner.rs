@@ -0,0 +1,211 @@
+//! Runs many challenges concurrently, e.g for a nightly check of every solver.
+//! Each run is isolated, so a solver that fails or panics doesn't stop the batch.
+
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use super::Registry;
+use super::registry::Entry;
+use super::error::{self, EXIT_REJECTED, EXIT_SOLVER};
+use super::timing::format_duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Accepted,
+    /// The solution was submitted but not accepted
+    Rejected,
+    /// A fixture was replayed, so nothing was submitted
+    Replayed,
+    Failed,
+    Panicked
+}
+
+impl Status {
+    pub fn is_success(&self) -> bool {
+        *self == Status::Accepted || *self == Status::Replayed
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub challenge: String,
+    pub status: Status,
+    /// The submission result, error or panic message
+    pub detail: String,
+    /// See `error`, 0 on success
+    pub exit_code: i32,
+    pub duration_ms: u64
+}
+
+impl Report {
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+}
+
+/// How many challenges run at the same time unless told otherwise, e.g with `--jobs`
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+pub struct Runner<'a> {
+    registry: &'a Registry,
+    concurrency: usize
+}
+
+impl<'a> Runner<'a> {
+    pub fn new(registry: &'a Registry) -> Runner<'a> {
+        Runner {
+            registry,
+            concurrency: DEFAULT_CONCURRENCY
+        }
+    }
+
+    /// How many challenges may run at the same time. Exclusive challenges(see
+    /// `Entry::exclusive`) still wait for every other run to finish, and block the rest
+    /// until they're done.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Runs every registered challenge
+    pub fn run_all(&self) -> Vec<Report> {
+        let names = self.registry.entries().iter().map(|entry| entry.name.to_owned()).collect::<Vec<_>>();
+        self.run(&names)
+    }
+
+    /// Runs the given challenges, returning their reports in the same order
+    pub fn run(&self, names: &[String]) -> Vec<Report> {
+        let jobs = names.iter().enumerate()
+            .map(|(index, name)| (index, name.clone(), self.registry.get(name).cloned()))
+            .collect::<Vec<_>>();
+        let jobs = Arc::new(Mutex::new(jobs.into_iter()));
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        // held for reading by every run, and for writing by exclusive ones
+        let exclusive = Arc::new(RwLock::new(()));
+        let workers = (0..self.concurrency.min(names.len())).map(|_| {
+            let jobs = jobs.clone();
+            let reports = reports.clone();
+            let exclusive = exclusive.clone();
+            thread::spawn(move || loop {
+                let job = jobs.lock().unwrap().next();
+                match job {
+                    Some((index, name, entry)) => {
+                        let report = if entry.map_or(false, |entry| entry.exclusive) {
+                            let _only_run = exclusive.write().unwrap();
+                            run_one(&name, entry)
+                        } else {
+                            let _shared_run = exclusive.read().unwrap();
+                            run_one(&name, entry)
+                        };
+                        reports.lock().unwrap().push((index, report));
+                    },
+                    None => break
+                }
+            })
+        }).collect::<Vec<_>>();
+        for worker in workers {
+            // panics are caught per run, so workers themselves don't fail
+            worker.join().unwrap();
+        }
+        let mut reports = Arc::try_unwrap(reports).ok().unwrap().into_inner().unwrap();
+        reports.sort_by_key(|&(index, _)| index);
+        reports.into_iter().map(|(_, report)| report).collect()
+    }
+}
+
+fn run_one(name: &str, entry: Option<Entry>) -> Report {
+    let start = Instant::now();
+    let (status, detail, exit_code) = match entry {
+        None => (Status::Failed, format!("no such challenge \"{}\"", name), error::EXIT_USAGE),
+        Some(entry) => match panic::catch_unwind(AssertUnwindSafe(entry.run)) {
+            Ok(Ok(Some(ref result))) if result.is_accepted() => (Status::Accepted, result.to_string(), 0),
+            // `process_challenge` fails on these through `check_outcome`, but entries added directly may not
+            Ok(Ok(Some(result))) => (Status::Failed, result.to_string(), error::EXIT_OTHER),
+            Ok(Ok(None)) => (Status::Replayed, String::new(), 0),
+            Ok(Err(ref e)) if error::exit_code(e) == EXIT_REJECTED => (Status::Rejected, e.to_string(), EXIT_REJECTED),
+            Ok(Err(e)) => (Status::Failed, e.to_string(), error::exit_code(&e)),
+            // a panic is a bug in the solver
+            Err(panic) => (Status::Panicked, panic_message(&panic), EXIT_SOLVER)
+        }
+    };
+    let elapsed = start.elapsed();
+    Report {
+        challenge: name.to_owned(),
+        status,
+        detail,
+        exit_code,
+        duration_ms: elapsed.as_secs() * 1000 + (elapsed.subsec_nanos() / 1000000) as u64
+    }
+}
+
+fn panic_message(panic: &Box<Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+/// A pass/fail table of reports
+pub struct Summary<'a>(pub &'a [Report]);
+
+impl<'a> fmt::Display for Summary<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<24} {:<9} {:>9}  {}", "challenge", "status", "time", "detail")?;
+        for report in self.0 {
+            let status = format!("{:?}", report.status).to_lowercase();
+            // only the first line, multi-line details(e.g hints) would break the table
+            let detail = report.detail.lines().next().unwrap_or("");
+            writeln!(f, "{:<24} {:<9} {:>9}  {}", report.challenge, status, format_duration(report.duration()), detail)?;
+        }
+        let passed = self.0.iter().filter(|report| report.status.is_success()).count();
+        write!(f, "{}/{} passed", passed, self.0.len())
+    }
+}
+
+#[test]
+fn isolates_failures_and_panics() {
+    let mut registry = Registry::new();
+    fn fails() -> Result<Option<::SubmissionResult>, ::failure::Error> {
+        Err(format_err!("solver failed"))
+    }
+    fn panics() -> Result<Option<::SubmissionResult>, ::failure::Error> {
+        panic!("solver panicked")
+    }
+    fn replays() -> Result<Option<::SubmissionResult>, ::failure::Error> {
+        Ok(None)
+    }
+    fn rejected() -> Result<Option<::SubmissionResult>, ::failure::Error> {
+        Err(::HackatticError::Rejected { reason: "wrong nonce".to_owned() }.into())
+    }
+    fn rate_limited() -> Result<Option<::SubmissionResult>, ::failure::Error> {
+        ::check_outcome(::SubmissionResult::from_response(429, "{}".to_owned(), false)).map(Some)
+    }
+    registry.add(Entry { name: "fails", run: fails, exclusive: false });
+    registry.add(Entry { name: "panics", run: panics, exclusive: false });
+    registry.add(Entry { name: "replays", run: replays, exclusive: true });
+    registry.add(Entry { name: "rejected", run: rejected, exclusive: false });
+    registry.add(Entry { name: "rate_limited", run: rate_limited, exclusive: false });
+    let names = ["panics", "missing", "fails", "replays", "rejected", "rate_limited"].iter().map(|name| name.to_string()).collect::<Vec<_>>();
+    let reports = Runner::new(&registry).concurrency(2).run(&names);
+    let statuses = reports.iter().map(|report| (report.challenge.as_str(), report.status)).collect::<Vec<_>>();
+    assert_eq!(statuses, vec![
+        ("panics", Status::Panicked),
+        ("missing", Status::Failed),
+        ("fails", Status::Failed),
+        ("replays", Status::Replayed),
+        ("rejected", Status::Rejected),
+        ("rate_limited", Status::Failed)
+    ]);
+    assert_eq!(reports[0].detail, "solver panicked");
+    assert_eq!(reports[0].exit_code, EXIT_SOLVER);
+    assert_eq!(reports[3].exit_code, 0);
+    assert_eq!(reports[4].exit_code, EXIT_REJECTED);
+    assert_eq!(reports[5].exit_code, error::EXIT_NETWORK);
+}
@@ -3,37 +3,178 @@ extern crate serde_json;
 extern crate base64;
 extern crate hex;
 
-use serde::{Serializer, Deserialize, Deserializer};
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+use super::inspect::Truncated;
 
-pub fn as_hex<S, T>(data: &T, serializer: S) -> Result<S::Ok, S::Error>
-    where T: AsRef<[u8]>,
-          S: Serializer
-{
-    serializer.serialize_str(&hex::encode(data))
+const DEBUG_MAX_BYTES: usize = 32;
+
+/* Byte buffers which are (de)serialized as encoded strings, e.g for binary data in problem JSON.
+ * Decoding is lenient: whitespace is ignored, and so is missing or extra base64 padding. */
+
+fn strip_whitespace(encoded: &str) -> String {
+    encoded.chars().filter(|c| !c.is_whitespace()).collect()
 }
 
+fn decode_hex(encoded: &str) -> Result<Vec<u8>, String> {
+    hex::decode(strip_whitespace(encoded)).map_err(|e| e.to_string())
+}
 
-pub fn as_base64<S, T>(data: &T, serializer: S) -> Result<S::Ok, S::Error>
-    where T: AsRef<[u8]>,
-          S: Serializer
-{
-    serializer.serialize_str(&base64::encode(data))
+fn decode_base64(encoded: &str, config: base64::Config) -> Result<Vec<u8>, String> {
+    let mut encoded = strip_whitespace(encoded);
+    let unpadded_len = encoded.trim_right_matches('=').len();
+    encoded.truncate(unpadded_len);
+    while encoded.len() % 4 != 0 {
+        encoded.push('=');
+    }
+    base64::decode_config(&encoded, config).map_err(|e| e.to_string())
 }
 
-pub fn from_hex<'de,D>(deserializer:  D) -> Result<Vec<u8>, D::Error>
-    where D: Deserializer<'de>
-{
-    use serde::de::Error;
-    String::deserialize(deserializer)
-        .and_then(|string| hex::decode(&string).map_err(|err| Error::custom(err.to_string())))
+macro_rules! byte_encoding {
+    ($(#[$attr:meta])* $name:ident, $encode:expr, $decode:expr) => {
+        $(#[$attr])*
+        #[derive(Clone, PartialEq, Eq, Hash, Default)]
+        pub struct $name(pub Vec<u8>);
+
+        impl $name {
+            pub fn into_inner(self) -> Vec<u8> {
+                self.0
+            }
+
+            pub fn encode(&self) -> String {
+                $encode(&self.0)
+            }
+        }
+
+        impl Deref for $name {
+            type Target = [u8];
+            fn deref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl DerefMut for $name {
+            fn deref_mut(&mut self) -> &mut [u8] {
+                &mut self.0
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl From<Vec<u8>> for $name {
+            fn from(data: Vec<u8>) -> Self {
+                $name(data)
+            }
+        }
+
+        impl<'a> From<&'a [u8]> for $name {
+            fn from(data: &[u8]) -> Self {
+                $name(data.to_owned())
+            }
+        }
+
+        impl From<$name> for Vec<u8> {
+            fn from(data: $name) -> Vec<u8> {
+                data.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ::failure::Error;
+            fn from_str(encoded: &str) -> Result<Self, ::failure::Error> {
+                $decode(encoded).map($name).map_err(::failure::err_msg)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(&self.encode())
+            }
+        }
+
+        // shows the encoded form as it appears in the JSON, unless it's a large blob(e.g a dump)
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                if self.0.len() > DEBUG_MAX_BYTES {
+                    write!(f, "{}({:?})", stringify!($name), Truncated::new(&self.0, DEBUG_MAX_BYTES))
+                } else {
+                    write!(f, "{}({:?})", stringify!($name), self.encode())
+                }
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.encode())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                use serde::de::Error;
+                let encoded = String::deserialize(deserializer)?;
+                $decode(&encoded).map($name).map_err(Error::custom)
+            }
+        }
+    }
 }
 
+byte_encoding!(
+    /// Lowercase hex, without a "0x" prefix
+    Hex, hex::encode, decode_hex);
+byte_encoding!(
+    /// Standard base64, with padding
+    Base64, |data: &[u8]| base64::encode_config(data, base64::STANDARD),
+    |encoded: &str| decode_base64(encoded, base64::STANDARD));
+byte_encoding!(
+    /// URL-safe base64(i.e '-' and '_' instead of '+' and '/'), with padding
+    Base64Url, |data: &[u8]| base64::encode_config(data, base64::URL_SAFE),
+    |encoded: &str| decode_base64(encoded, base64::URL_SAFE));
+byte_encoding!(
+    /// Standard base64, without padding
+    Base64NoPad, |data: &[u8]| base64::encode_config(data, base64::STANDARD_NO_PAD),
+    |encoded: &str| decode_base64(encoded, base64::STANDARD));
+
+#[test]
+fn encodes_each_variant() {
+    let data = vec![0xfb, 0xff, 0x01];
+    assert_eq!(serde_json::to_value(Hex::from(data.clone())).unwrap(), json!("fbff01"));
+    assert_eq!(serde_json::to_value(Base64::from(data.clone())).unwrap(), json!("+/8B"));
+    assert_eq!(serde_json::to_value(Base64Url::from(data.clone())).unwrap(), json!("-_8B"));
+    assert_eq!(serde_json::to_value(Base64NoPad::from(vec![0xfb])).unwrap(), json!("+w"));
+    assert_eq!(format!("{:?}", Hex::from(data)), "Hex(\"fbff01\")");
+    assert_eq!(format!("{:?}", Base64::from(vec![0; 1000])), "Base64(<1000 bytes: 00000000000000000000000000000000..(968 bytes)..00000000000000000000000000000000>)");
+}
 
-pub fn from_base64<'de,D>(deserializer:  D) -> Result<Vec<u8>, D::Error>
-    where D: Deserializer<'de>
-{
-    use serde::de::Error;
-    String::deserialize(deserializer)
-        .and_then(|string| base64::decode(&string).map_err(|err| Error::custom(err.to_string())))
+#[test]
+fn decodes_leniently() {
+    let decode = |json| serde_json::from_value::<Base64>(json).unwrap().into_inner();
+    assert_eq!(decode(json!("aGVs\nbG8=")), b"hello");
+    assert_eq!(decode(json!("aGVsbG8")), b"hello");
+    assert_eq!(serde_json::from_value::<Base64NoPad>(json!("aGVsbG8=")).unwrap().into_inner(), b"hello");
+    assert_eq!(serde_json::from_value::<Hex>(json!("de ad\tbe ef")).unwrap().into_inner(), vec![0xde, 0xad, 0xbe, 0xef]);
+    assert!(serde_json::from_value::<Hex>(json!("xyz")).is_err());
 }
 
+#[test]
+fn supports_option_and_vec() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Files {
+        files: Vec<Base64>,
+        checksum: Option<Hex>
+    }
+    let files = Files {
+        files: vec![Base64::from(&b"a"[..]), Base64::from(&b"b"[..])],
+        checksum: None
+    };
+    let json = serde_json::to_value(&files).unwrap();
+    assert_eq!(json, json!({ "files": ["YQ==", "Yg=="], "checksum": null }));
+    assert_eq!(serde_json::from_value::<Files>(json).unwrap(), files);
+    assert_eq!(&*files.files[0], b"a");
+}
@@ -0,0 +1,128 @@
+use serde_json::{self, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use super::timing::Timings;
+
+/// The known fields of a solve endpoint response. Any other fields, such as the
+/// diagnostics Hackattic adds in playground mode, end up in `hints`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResponseFields {
+    #[serde(default)]
+    pub result: Option<String>,
+    #[serde(default)]
+    pub rejected: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(flatten)]
+    pub hints: BTreeMap<String, Value>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "outcome", content = "reason")]
+pub enum Outcome {
+    Accepted,
+    Rejected(String),
+    RateLimited,
+    /// The request was refused before the solution was judged, e.g because of a missing
+    /// access token(401/403) or an unknown challenge(404)
+    Refused(String),
+    /// The response couldn't be understood, see `SubmissionResult::raw`
+    Malformed
+}
+
+/// The result of submitting a solution, classified from the solve endpoint's response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmissionResult {
+    pub outcome: Outcome,
+    /// HTTP status code of the response
+    pub status: u16,
+    /// Whether the solution was submitted with `playground=1`, not counting as an attempt
+    pub playground: bool,
+    pub fields: ResponseFields,
+    /// The body as received
+    pub raw: String,
+    /// How long each phase of the run took, filled in by `process_challenge`
+    pub timings: Timings
+}
+
+impl SubmissionResult {
+    pub fn from_response(status: u16, raw: String, playground: bool) -> SubmissionResult {
+        let fields = serde_json::from_str::<ResponseFields>(&raw).ok();
+        // the status is checked first, as e.g a 401 also has an `error` field
+        let outcome = match fields {
+            _ if status == 429 => Outcome::RateLimited,
+            _ if status == 401 || status == 403 || status == 404 => {
+                let reason = fields.as_ref().and_then(|fields| fields.error.clone().or_else(|| fields.rejected.clone()));
+                Outcome::Refused(reason.unwrap_or_else(|| raw.trim().to_owned()))
+            },
+            Some(ResponseFields { error: Some(ref error), .. }) if is_rate_limit_message(error) => Outcome::RateLimited,
+            Some(ResponseFields { rejected: Some(ref reason), .. }) |
+            Some(ResponseFields { error: Some(ref reason), .. }) => Outcome::Rejected(reason.clone()),
+            Some(ResponseFields { result: Some(ref result), .. }) if status < 400 && result.contains("passed") => Outcome::Accepted,
+            Some(ResponseFields { result: Some(ref result), .. }) => Outcome::Rejected(result.clone()),
+            _ => Outcome::Malformed
+        };
+        SubmissionResult {
+            outcome,
+            status,
+            playground,
+            fields: fields.unwrap_or_default(),
+            raw,
+            timings: Timings::default()
+        }
+    }
+
+    pub fn is_accepted(&self) -> bool {
+        self.outcome == Outcome::Accepted
+    }
+
+    /// Server-side hints, i.e any response fields besides the outcome
+    pub fn hints(&self) -> &BTreeMap<String, Value> {
+        &self.fields.hints
+    }
+}
+
+fn is_rate_limit_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("rate limit") || message.contains("too many")
+}
+
+impl fmt::Display for SubmissionResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.playground {
+            write!(f, "[playground] ")?;
+        }
+        match self.outcome {
+            Outcome::Accepted => write!(f, "accepted")?,
+            Outcome::Rejected(ref reason) => write!(f, "rejected: {}", reason)?,
+            Outcome::RateLimited => write!(f, "rate limited (HTTP {})", self.status)?,
+            Outcome::Refused(ref reason) => write!(f, "refused (HTTP {}): {}", self.status, reason)?,
+            Outcome::Malformed => return write!(f, "malformed response (HTTP {}): {}", self.status, self.raw)
+        }
+        for (name, value) in self.hints() {
+            write!(f, "\n    {}: {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn classifies_responses() {
+    let classify = |status, raw: &str| SubmissionResult::from_response(status, raw.to_owned(), false).outcome;
+    assert_eq!(classify(200, r#"{"result": "passed"}"#), Outcome::Accepted);
+    assert_eq!(classify(200, r#"{"rejected": "wrong hash"}"#), Outcome::Rejected("wrong hash".to_owned()));
+    assert_eq!(classify(400, r#"{"error": "no problem"}"#), Outcome::Rejected("no problem".to_owned()));
+    assert_eq!(classify(429, r#"{"error": "slow down"}"#), Outcome::RateLimited);
+    assert_eq!(classify(200, r#"{"error": "Rate limit exceeded"}"#), Outcome::RateLimited);
+    assert_eq!(classify(502, "Bad Gateway"), Outcome::Malformed);
+    assert_eq!(classify(401, r#"{"error": "missing access_token"}"#), Outcome::Refused("missing access_token".to_owned()));
+    assert_eq!(classify(404, "Not Found"), Outcome::Refused("Not Found".to_owned()));
+}
+
+#[test]
+fn keeps_playground_hints() {
+    let raw = r#"{"rejected": "wrong hash", "hint": "check your nonce"}"#.to_owned();
+    let result = SubmissionResult::from_response(200, raw, true);
+    assert!(!result.is_accepted());
+    assert_eq!(result.hints()["hint"], json!("check your nonce"));
+}
@@ -1,16 +1,6 @@
-extern crate reqwest;
-extern crate openssl;
-#[macro_use]
-extern crate serde_derive;
-extern crate serde;
-extern crate serde_json;
-extern crate ring;
-extern crate base64;
-extern crate hackattic;
-extern crate failure;
-
 use std::fs::File;
 use std::io::Write;
+use std::path::PathBuf;
 use failure::Error;
 use std::collections::HashMap;
 use openssl::x509::{X509Builder, X509, X509NameBuilder};
@@ -18,7 +8,7 @@ use openssl::asn1::Asn1Time;
 use openssl::bn::BigNum;
 use openssl::pkey::PKey;
 use openssl::hash::MessageDigest;
-use hackattic::HackatticChallenge;
+use base64;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct CertRequirements {
@@ -73,11 +63,13 @@ fn country_name_to_code(name: &str) -> Option<String> {
     mappings.get(name).map(|n| n.to_string())
 }
 
-fn dump_cert_to_file(cert: &X509) -> Result<(), Error> {
+// written to the temp dir rather than the working directory, so that running the tests doesn't touch the repo
+fn dump_cert_to_file(cert: &X509) -> Result<PathBuf, Error> {
     let der = cert.to_der()?;
-    let mut der_file = File::create("cert.der")?;
+    let path = ::std::env::temp_dir().join("hackattic_tales_of_ssl_cert.der");
+    let mut der_file = File::create(&path)?;
     der_file.write_all(&der)?;
-    Ok(())
+    Ok(path)
 }
 
 fn get_private_key_from_string(string: &str) -> Result<PKey, Error> {
@@ -100,23 +92,13 @@ fn x509_to_answer(cert: X509) -> Result<Answer, Error> {
     })
 }
 
-fn main() {
-    TalesOfSsl::process_challenge().unwrap();
-}
-
-struct TalesOfSsl;
-impl HackatticChallenge for TalesOfSsl {
-    type Problem = CertRequirements;
-    type Solution = Answer;
-    fn challenge_name() -> &'static str {
-        "tales_of_ssl"
-    }
-    fn make_solution(req: &CertRequirements) -> Result<Answer, ::failure::Error> {
-        let x509 = create_certificate(&req)?;
-        dump_cert_to_file(&x509)?;
-        let answer = x509_to_answer(x509)?;
-        Ok(answer)
-    }
+#[hackattic_challenge(name = "tales_of_ssl")]
+fn solve(req: &CertRequirements) -> Result<Answer, Error> {
+    let x509 = create_certificate(&req)?;
+    let path = dump_cert_to_file(&x509)?;
+    debug!("wrote the certificate to {}", path.display());
+    let answer = x509_to_answer(x509)?;
+    Ok(answer)
 }
 
 #[test]
@@ -159,4 +141,4 @@ jyA9qXqpWbpYDVmP6NaKewg8xWqPusJXzjeP8m0rIh2Huu8WdNs=
     };
     let cert = create_certificate(&req);
     assert!(cert.is_ok(), "Failed to create certificate");
-}
\ No newline at end of file
+}
@@ -0,0 +1,197 @@
+//! Timing of the phases of a challenge run. Many problems expire a few seconds
+//! after being fetched, so solving is aborted after `Config::solve_deadline_ms`.
+
+use failure::Error;
+use std::cell::RefCell;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use super::Config;
+use super::logging;
+
+/// Fraction of the deadline after which a slow solve is warned about
+pub const WARN_FRACTION: f64 = 0.8;
+
+thread_local! {
+    // set by `solve_within` on its solver thread
+    static CANCELLED: RefCell<Option<Arc<AtomicBool>>> = RefCell::new(None);
+}
+
+/// A flag set once the solve running on this thread is past its deadline, see `solve_within`.
+/// Solvers that run for long(e.g mini_miner) should check it and give up once it's set, outside
+/// of `solve_within` it's never set.
+pub fn cancellation() -> Arc<AtomicBool> {
+    CANCELLED.with(|cancelled| cancelled.borrow().clone())
+        .unwrap_or_else(|| Arc::new(AtomicBool::new(false)))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timings {
+    pub fetch: Option<Duration>,
+    pub solve: Option<Duration>,
+    pub submit: Option<Duration>
+}
+
+impl Timings {
+    pub fn total(&self) -> Duration {
+        [self.fetch, self.solve, self.submit].iter()
+            .filter_map(|phase| *phase)
+            .fold(Duration::from_secs(0), |total, phase| total + phase)
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(name, phase) in &[("fetch", self.fetch), ("solve", self.solve), ("submit", self.submit)] {
+            match phase {
+                Some(phase) => write!(f, "{} {}, ", name, format_duration(phase))?,
+                None => write!(f, "{} -, ", name)?
+            }
+        }
+        write!(f, "total {}", format_duration(self.total()))
+    }
+}
+
+#[derive(Debug, Fail)]
+#[fail(display = "solving \"{}\" took {}, past the {} deadline, the problem has likely expired so nothing was submitted",
+       challenge, elapsed, deadline)]
+pub struct DeadlineExceeded {
+    pub challenge: String,
+    pub elapsed: String,
+    pub deadline: String
+}
+
+impl DeadlineExceeded {
+    pub fn new(challenge: &str, elapsed: Duration, deadline: Duration) -> DeadlineExceeded {
+        DeadlineExceeded {
+            challenge: challenge.to_owned(),
+            elapsed: format_duration(elapsed),
+            deadline: format_duration(deadline)
+        }
+    }
+}
+
+/// Runs `f`, returning its result along with how long it took
+pub fn measure<T, F: FnOnce() -> T>(f: F) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+/// Fails if solving took longer than `deadline`, warns if it came close.
+pub fn check_deadline(challenge: &str, elapsed: Duration, deadline: Option<Duration>) -> Result<(), Error> {
+    let deadline = match deadline {
+        Some(deadline) => deadline,
+        None => return Ok(())
+    };
+    if elapsed > deadline {
+        return Err(DeadlineExceeded::new(challenge, elapsed, deadline).into());
+    }
+    if as_secs_f64(elapsed) > as_secs_f64(deadline) * WARN_FRACTION {
+        warn!("solving \"{}\" took {}, close to the {} deadline", challenge, format_duration(elapsed), format_duration(deadline));
+    }
+    Ok(())
+}
+
+/// Runs `solve` on a worker thread, failing with `DeadlineExceeded` as soon as `deadline`
+/// passes instead of waiting for it to return. A thread can't be killed, so an overrunning
+/// solver is told to stop through `cancellation` and its result is dropped.
+pub fn solve_within<T, F>(challenge: &str, deadline: Option<Duration>, solve: F) -> Result<T, Error>
+    where T: Send + 'static, F: FnOnce() -> Result<T, Error> + Send + 'static
+{
+    let deadline = match deadline {
+        Some(deadline) => deadline,
+        None => return solve()
+    };
+    let (sender, receiver) = mpsc::channel();
+    let spans = logging::current_spans();
+    // so that the solver sees the same config, even one scoped to this thread
+    let config = Config::current().ok();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let solver_cancelled = cancelled.clone();
+    let start = Instant::now();
+    thread::Builder::new().name(format!("{} solver", challenge)).spawn(move || {
+        logging::inherit_spans(spans);
+        let _config = config.map(Config::scoped);
+        CANCELLED.with(|cancelled| *cancelled.borrow_mut() = Some(solver_cancelled));
+        // the receiver is gone if the deadline passed, there's no one left to tell
+        let _ = sender.send(panic::catch_unwind(AssertUnwindSafe(solve)));
+    })?;
+    match receiver.recv_timeout(deadline) {
+        Ok(Ok(result)) => result,
+        // so that it's still reported as a panic, e.g by the `Runner`
+        Ok(Err(panic)) => panic::resume_unwind(panic),
+        Err(RecvTimeoutError::Timeout) => {
+            cancelled.store(true, Ordering::Relaxed);
+            Err(DeadlineExceeded::new(challenge, start.elapsed(), deadline).into())
+        },
+        Err(RecvTimeoutError::Disconnected) => bail!("solver of \"{}\" stopped without a result", challenge)
+    }
+}
+
+pub fn as_secs_f64(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1e9
+}
+
+/// e.g "850ms" or "3.21s"
+pub fn format_duration(duration: Duration) -> String {
+    let secs = as_secs_f64(duration);
+    if secs < 1.0 {
+        format!("{}ms", (secs * 1000.0).round())
+    } else {
+        format!("{:.2}s", secs)
+    }
+}
+
+#[test]
+fn checks_deadline() {
+    let deadline = Some(Duration::from_millis(1000));
+    assert!(check_deadline("mini_miner", Duration::from_millis(500), None).is_ok());
+    assert!(check_deadline("mini_miner", Duration::from_millis(900), deadline).is_ok());
+    let err = check_deadline("mini_miner", Duration::from_millis(1500), deadline).unwrap_err();
+    assert!(err.downcast_ref::<DeadlineExceeded>().is_some());
+}
+
+#[test]
+fn aborts_solves_past_deadline() {
+    let deadline = Some(Duration::from_millis(50));
+    assert_eq!(solve_within("mini_miner", deadline, || Ok(42)).unwrap(), 42);
+    let start = Instant::now();
+    let err = solve_within("mini_miner", deadline, || {
+        thread::sleep(Duration::from_secs(5));
+        Ok(42)
+    }).unwrap_err();
+    assert!(err.downcast_ref::<DeadlineExceeded>().is_some());
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn cancels_solves_past_deadline() {
+    assert!(!cancellation().load(Ordering::Relaxed));
+    let (stopped, receiver) = mpsc::channel();
+    let err = solve_within("mini_miner", Some(Duration::from_millis(50)), move || -> Result<(), Error> {
+        let cancelled = cancellation();
+        while !cancelled.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        stopped.send(()).unwrap();
+        bail!("cancelled")
+    }).unwrap_err();
+    assert!(err.downcast_ref::<DeadlineExceeded>().is_some());
+    // the solver noticed and stopped instead of running on in the background
+    receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+}
+
+#[test]
+fn formats_timings() {
+    let timings = Timings {
+        fetch: Some(Duration::from_millis(120)),
+        solve: Some(Duration::from_millis(3210)),
+        submit: None
+    };
+    assert_eq!(timings.to_string(), "fetch 120ms, solve 3.21s, submit -, total 3.33s");
+}
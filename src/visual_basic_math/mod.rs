@@ -1,8 +1,5 @@
-use super::HackatticChallenge;
-use failure::{Error, ResultExt};
-use std::io::{Read, Write, BufWriter};
-use std::process::{Command, Stdio};
-use reqwest;
+use super::fetch_asset;
+use failure::Error;
 
 pub mod ocr;
 
@@ -74,33 +71,15 @@ struct Solution {
     result: i64
 }
 
-pub fn main() {
-    main_err().unwrap();
-}
-
-pub fn main_err() -> Result<(), Error> {
-    VisualBasicMath::process_challenge()?;
-    Ok(())
-}
-
-
-struct VisualBasicMath;
-impl HackatticChallenge for VisualBasicMath {
-    type Problem = Problem;
-    type Solution = Solution;
-    fn challenge_name() -> &'static str {
-        "visual_basic_math"
-    }
-    fn make_solution(req: &Problem) -> Result<Solution, Error> {
-        let mut image_buf = Vec::new();
-        reqwest::get(&req.image_url)?.read_to_end(&mut image_buf)?;
-        let text = ocr::image_to_text(&image_buf)?;
-        let expressions = Expression::from_lines(&text)?;
-        let result = Expression::fold_expressions(&expressions);
-        Ok(Solution {
-            result
-        })
-    }
+#[hackattic_challenge(name = "visual_basic_math")]
+fn solve(req: &Problem) -> Result<Solution, Error> {
+    let image_buf = fetch_asset(&req.image_url)?;
+    let text = ocr::image_to_text(&image_buf)?;
+    let expressions = Expression::from_lines(&text)?;
+    let result = Expression::fold_expressions(&expressions);
+    Ok(Solution {
+        result
+    })
 }
 
 #[test]
@@ -110,4 +89,4 @@ fn can_compute_expressions() {
     *2";
     let exprs = Expression::from_lines(input).unwrap();
     assert_eq!(Expression::fold_expressions(&exprs), 4);
-}
\ No newline at end of file
+}
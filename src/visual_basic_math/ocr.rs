@@ -1,7 +1,6 @@
 use failure::Error;
-use std::process::{Command, Stdio};
-use std::io::{Read,Write,BufRead,BufWriter};
-use std::path::PathBuf;
+use ::error::run_tool;
+use std::process::Command;
 
 /* available characters: 0123456789+-÷×
    length of each line is 8, the first being a math operator, followed by 7 digits
@@ -15,17 +14,11 @@ use std::path::PathBuf;
 */
 
 pub fn image_to_text(image: &[u8]) -> Result<String, Error> {
-    let tess = Command::new("tesseract")
+    let tess = run_tool("tesseract", Command::new("tesseract")
         .args(&["stdin", "stdout", "--oem", "1", "--psm", "4", "-l", "eng",
                 "-c", "tessedit_char_whitelist=0123456789+-÷×", "stdout"])
-        .env("TESSDATA_PREFIX", "./extra/visual_basic_math/trained")
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()?;
-    tess.stdin.unwrap().write_all(image)?;
-    let mut string = String::new();
-    tess.stdout.unwrap().read_to_string(&mut string)?;
-    string = string.replace(" ","")
+        .env("TESSDATA_PREFIX", "./extra/visual_basic_math/trained"), Some(image))?;
+    let string = String::from_utf8_lossy(&tess.stdout).replace(" ","")
         .replace("├╖", "/")
         .replace("÷", "/")
         .replace("├ק", "*")
@@ -37,6 +30,6 @@ pub fn image_to_text(image: &[u8]) -> Result<String, Error> {
         .map(|line| line.chars().take(1).chain(line.chars().skip(1).filter(|c| c.is_digit(10))).collect::<String>())
         .collect::<Vec<_>>();
     let string = lines.join("\n");
-    println!("OCR result is:\n{}",string);
+    debug!("OCR result is:\n{}",string);
     Ok(string)
 }
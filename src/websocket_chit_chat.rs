@@ -1,22 +1,6 @@
-extern crate hackattic;
-#[macro_use]
-extern crate serde_derive;
-extern crate serde_json;
-extern crate serde;
-#[macro_use]
-extern crate failure;
-#[macro_use]
-extern crate log;
-extern crate ansi_term;
-extern crate pretty_env_logger;
-
-extern crate websocket;
-extern crate tokio_core;
-extern crate futures;
-
-use hackattic::HackatticChallenge;
+use super::{AsyncHackatticChallenge, BoxFuture, Config, Registry};
 use std::time::{Instant, Duration};
-use tokio_core::reactor::Core;
+use tokio_core::reactor::Handle;
 use websocket::async::Client;
 use futures::{Future, Stream, Sink};
 use websocket::async::stream::{AsyncRead, AsyncWrite};
@@ -24,7 +8,7 @@ use websocket::{ClientBuilder, OwnedMessage};
 use futures::future::{ok, err, loop_fn, Loop};
 use failure::Error;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 struct Problem {
     token: String
 }
@@ -34,14 +18,8 @@ struct Solution {
     secret: String
 }
 
-fn main() {
-    ansi_term::enable_ansi_support().unwrap();
-    let mut builder = pretty_env_logger::formatted_builder().unwrap();
-    builder.filter(Some("websocket"), log::LevelFilter::Info);
-    builder.init();
-    info!("Logger initialized");
-    Websocket::process_challenge().unwrap();
-
+pub fn register(registry: &mut Registry) {
+    registry.register_async::<Websocket>();
 }
 
 struct PingState {
@@ -103,16 +81,22 @@ impl PingState {
 }
 
 struct Websocket;
-impl HackatticChallenge for Websocket {
+impl AsyncHackatticChallenge for Websocket {
     type Problem = Problem;
     type Solution = Solution;
 
 
-    fn make_solution(problem: &Self::Problem) -> Result<Self::Solution, Error> {
-        let mut core = Core::new()?;
-        let url = format!("wss://hackattic.com/_/ws/{}", problem.token);
-        let client_future = ClientBuilder::new(&url)?
-            .async_connect(None,&core.handle())
+    fn make_solution(problem: Self::Problem, handle: &Handle) -> BoxFuture<Self::Solution> {
+        let url = match Config::current().and_then(|config| config.websocket_url(&format!("/_/ws/{}", problem.token))) {
+            Ok(url) => url,
+            Err(e) => return Box::new(err(e))
+        };
+        let builder = match ClientBuilder::new(&url) {
+            Ok(builder) => builder,
+            Err(e) => return Box::new(err(Error::from(e)))
+        };
+        let client_future = builder
+            .async_connect(None, handle)
             .map_err(|e| Error::from(e))
             .and_then(|(client, _)| {
                 info!("Client obtained, looping...");
@@ -132,16 +116,13 @@ impl HackatticChallenge for Websocket {
                             }
                         })
                 })
-            }).map_err(|err| Error::from(err));
-
-
-        let secret = core.run(client_future)?;
-        Ok(Solution {
-            secret
-        })
+            }).map(|secret| Solution {
+                secret
+            });
+        Box::new(client_future)
     }
 
     fn challenge_name() -> &'static str {
         "websocket_chit_chat"
     }
-}
\ No newline at end of file
+}